@@ -0,0 +1,127 @@
+use std::fmt;
+
+/// A calendar date with an optional time of day, as found in CSV cells.
+///
+/// Dates are read as `YYYY-MM-DD`, or with `/` or `.` between the parts,
+/// optionally followed by `T` or a space and `HH:MM` or `HH:MM:SS`. Dates
+/// order chronologically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// The year.
+    pub year: i32,
+    /// The month, from 1.
+    pub month: u8,
+    /// The day of the month, from 1.
+    pub day: u8,
+    /// The hour, minute and second, if a time was given.
+    pub time: Option<(u8, u8, u8)>,
+}
+
+impl Date {
+    /// Reads `text` as a date, or returns `None` if it is not one.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (date, time) = match text.find(['T', ' ']) {
+            Some(split) => (&text[..split], Some(text[split + 1..].trim_start())),
+            None => (text, None),
+        };
+
+        let separator = date.chars().find(|c| matches!(c, '-' | '/' | '.'))?;
+        let mut parts = date.split(separator);
+        let year = parts.next().filter(|year| year.len() == 4)?;
+        let year = number(year, 4)?;
+        let month = number(parts.next()?, 2)?;
+        let day = number(parts.next()?, 2)?;
+        if parts.next().is_some() || !(1..=12).contains(&month) {
+            return None;
+        }
+        if day < 1 || day > days_in_month(year as i32, month as u8) as u32 {
+            return None;
+        }
+
+        let time = match time {
+            Some(time) => {
+                let mut parts = time.split(':');
+                let hour = number(parts.next()?, 2)?;
+                let minute = number(parts.next()?, 2)?;
+                let second = match parts.next() {
+                    Some(second) => number(second, 2)?,
+                    None => 0,
+                };
+                if parts.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+                    return None;
+                }
+                Some((hour as u8, minute as u8, second as u8))
+            }
+            None => None,
+        };
+
+        Some(Date {
+            year: year as i32,
+            month: month as u8,
+            day: day as u8,
+            time,
+        })
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)?;
+        if let Some((hour, minute, second)) = self.time {
+            write!(f, "T{:02}:{:02}:{:02}", hour, minute, second)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads `digits`, which must be all ASCII digits and at most `max_len` long.
+fn number(digits: &str, max_len: usize) -> Option<u32> {
+    if digits.is_empty() || digits.len() > max_len || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_dates_with_any_separator_and_an_optional_time() {
+        let date = Date::parse("2024/02/29 13:05").unwrap();
+        assert_eq!(date.to_string(), "2024-02-29T13:05:00");
+        assert_eq!(Date::parse(" 2024.1.5 ").unwrap().to_string(), "2024-01-05");
+    }
+
+    #[test]
+    fn rejects_impossible_dates_and_times() {
+        for text in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-04-31",
+            "24-01-01",
+            "2024-01-01T24:00",
+            "2024-01-01-01",
+            "1/2/2024",
+        ] {
+            assert_eq!(Date::parse(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn orders_a_date_before_any_time_that_day() {
+        let day = Date::parse("2024-01-01").unwrap();
+        let noon = Date::parse("2024-01-01T12:00").unwrap();
+        assert!(day < noon && noon < Date::parse("2024-01-02").unwrap());
+    }
+}
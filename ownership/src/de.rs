@@ -0,0 +1,351 @@
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::error::FieldError;
+
+/// Deserializes one row. With headers, structs and maps are filled by
+/// header name; without, struct fields are taken in column order.
+pub(crate) fn from_row<'de, T>(
+    headers: Option<&'de [String]>,
+    row: &'de [String],
+) -> Result<T, FieldError>
+where
+    T: serde::Deserialize<'de>,
+{
+    T::deserialize(RowDeserializer { headers, row })
+}
+
+struct RowDeserializer<'de> {
+    headers: Option<&'de [String]>,
+    row: &'de [String],
+}
+
+impl<'de> Deserializer<'de> for RowDeserializer<'de> {
+    type Error = FieldError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        match self.headers {
+            Some(_) => self.deserialize_map(visitor),
+            None => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        let headers = self.headers.ok_or_else(|| FieldError {
+            column: None,
+            message: "mapping a row by name requires headers".to_string(),
+        })?;
+        visitor.visit_map(Fields {
+            headers,
+            row: self.row,
+            column: 0,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, FieldError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        visitor.visit_seq(Cells {
+            row: self.row,
+            column: 0,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, FieldError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, FieldError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, FieldError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct enum identifier ignored_any
+    }
+}
+
+/// Header names paired with the cells under them, as a map.
+struct Fields<'de> {
+    headers: &'de [String],
+    row: &'de [String],
+    column: usize,
+}
+
+impl<'de> MapAccess<'de> for Fields<'de> {
+    type Error = FieldError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, FieldError> {
+        // Short rows simply lack the trailing fields.
+        if self.column >= self.headers.len().min(self.row.len()) {
+            return Ok(None);
+        }
+        let header = self.headers[self.column].as_str();
+        seed.deserialize(BorrowedStrDeserializer::new(header))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, FieldError> {
+        let column = self.column;
+        self.column += 1;
+        seed.deserialize(CellDeserializer::new(&self.row[column], column))
+            .map_err(|err| err.at(column))
+    }
+}
+
+/// The cells of a row in order, as a sequence.
+struct Cells<'de> {
+    row: &'de [String],
+    column: usize,
+}
+
+impl<'de> SeqAccess<'de> for Cells<'de> {
+    type Error = FieldError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, FieldError> {
+        let Some(cell) = self.row.get(self.column) else {
+            return Ok(None);
+        };
+        let column = self.column;
+        self.column += 1;
+        seed.deserialize(CellDeserializer::new(cell, column))
+            .map(Some)
+            .map_err(|err| err.at(column))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.row.len() - self.column)
+    }
+}
+
+/// Reads a boolean cell: `true` or `false`, also written `yes` or `no`, in
+/// any case.
+pub(crate) fn parse_bool(cell: &str) -> Option<bool> {
+    match cell.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// A single cell. Numbers and booleans are parsed from the text, as
+/// [`parse_bool`] reads them, and an empty cell is `None` for optional
+/// fields.
+struct CellDeserializer<'de> {
+    value: &'de str,
+    column: usize,
+}
+
+impl<'de> CellDeserializer<'de> {
+    fn new(value: &'de str, column: usize) -> Self {
+        CellDeserializer { value, column }
+    }
+
+    fn error(&self, message: String) -> FieldError {
+        FieldError {
+            column: Some(self.column),
+            message,
+        }
+    }
+
+    fn parse<T>(&self, expected: &str) -> Result<T, FieldError>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.value.trim().parse().map_err(|err| {
+            self.error(format!(
+                "expected {}, found {:?}: {}",
+                expected, self.value, err
+            ))
+        })
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident, $expected:literal;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+                visitor.$visit(self.parse($expected)?)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for CellDeserializer<'de> {
+    type Error = FieldError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        match parse_bool(self.value) {
+            Some(boolean) => visitor.visit_bool(boolean),
+            None => Err(self.error(format!("expected a boolean, found {:?}", self.value))),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8, "an integer";
+        deserialize_i16 => visit_i16, "an integer";
+        deserialize_i32 => visit_i32, "an integer";
+        deserialize_i64 => visit_i64, "an integer";
+        deserialize_i128 => visit_i128, "an integer";
+        deserialize_u8 => visit_u8, "an unsigned integer";
+        deserialize_u16 => visit_u16, "an unsigned integer";
+        deserialize_u32 => visit_u32, "an unsigned integer";
+        deserialize_u64 => visit_u64, "an unsigned integer";
+        deserialize_u128 => visit_u128, "an unsigned integer";
+        deserialize_f32 => visit_f32, "a number";
+        deserialize_f64 => visit_f64, "a number";
+        deserialize_char => visit_char, "a single character";
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        if self.value.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        if self.value.is_empty() {
+            visitor.visit_unit()
+        } else {
+            Err(self.error(format!("expected an empty cell, found {:?}", self.value)))
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, FieldError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, FieldError> {
+        // Cells can only name unit variants.
+        visitor.visit_enum(BorrowedStrDeserializer::new(self.value))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::testing::row;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Person {
+        #[serde(rename = "Full Name")]
+        name: String,
+        age: Option<u32>,
+        member: bool,
+    }
+
+    #[test]
+    fn fills_struct_fields_by_header() {
+        let headers = row(&["member", "Full Name", "age"]);
+        let person: Person = from_row(Some(&headers), &row(&["yes", "Ann", " 31 "])).unwrap();
+        let expected = Person {
+            name: "Ann".to_string(),
+            age: Some(31),
+            member: true,
+        };
+        assert_eq!(person, expected);
+    }
+
+    #[test]
+    fn reads_empty_and_missing_cells_as_none() {
+        let headers = row(&["Full Name", "member", "age"]);
+        let person: Person = from_row(Some(&headers), &row(&["Ann", "No", ""])).unwrap();
+        assert_eq!(person.age, None);
+        assert!(!person.member);
+        let person: Person = from_row(Some(&headers), &row(&["Bob", "false"])).unwrap();
+        assert_eq!(person.age, None);
+    }
+
+    #[test]
+    fn takes_cells_in_order_without_headers() {
+        let record: (String, i64, Option<f64>) = from_row(None, &row(&["a", "-3", ""])).unwrap();
+        assert_eq!(record, ("a".to_string(), -3, None));
+        let person: Person = from_row(None, &row(&["Ann", "31", "TRUE"])).unwrap();
+        assert_eq!(person.age, Some(31));
+    }
+
+    #[test]
+    fn names_the_column_of_a_bad_cell() {
+        let headers = row(&["Full Name", "age", "member"]);
+        let err = from_row::<Person>(Some(&headers), &row(&["Ann", "old", "true"])).unwrap_err();
+        assert_eq!(err.column, Some(1));
+        assert!(err.message.contains("expected an unsigned integer"));
+        let err = from_row::<Person>(Some(&headers), &row(&["Ann", "3", "maybe"])).unwrap_err();
+        assert_eq!(err.column, Some(2));
+        assert_eq!(err.message, "expected a boolean, found \"maybe\"");
+    }
+
+    #[test]
+    fn reads_booleans_in_any_case() {
+        for (cell, expected) in [
+            ("true", true),
+            (" Yes", true),
+            ("FALSE", false),
+            ("no", false),
+        ] {
+            assert_eq!(parse_bool(cell), Some(expected));
+        }
+        assert_eq!(parse_bool("1"), None);
+        assert_eq!(parse_bool(""), None);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::writer::QuoteStyle;
+
+/// How a quote character is escaped inside a quoted field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Escape {
+    /// A quote is written twice, as in RFC 4180: `"He said ""hi"""`.
+    #[default]
+    Doubled,
+    /// A quote (or the escape character itself) is preceded by this
+    /// character, usually a backslash: `"He said \"hi\""`.
+    Char(char),
+}
+
+/// The line terminator written after each record. Both are accepted on input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineTerminator {
+    /// `\n`, as on Unix.
+    #[default]
+    Lf,
+    /// `\r\n`, as on Windows and in RFC 4180.
+    CrLf,
+}
+
+impl LineTerminator {
+    /// The terminator's characters.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineTerminator::Lf => "\n",
+            LineTerminator::CrLf => "\r\n",
+        }
+    }
+}
+
+/// The flavour of CSV being read and written.
+///
+/// The default is RFC 4180: comma separated, `"` quoted with doubled quotes,
+/// no comments, LF line endings, and quotes only where needed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dialect {
+    /// Separates fields within a record.
+    pub delimiter: char,
+    /// Wraps fields that contain special characters.
+    pub quote: char,
+    /// How quotes are escaped inside quoted fields.
+    pub escape: Escape,
+    /// Lines starting with this character are skipped when reading.
+    pub comment: Option<char>,
+    /// Written after each record.
+    pub terminator: LineTerminator,
+    /// Which fields are quoted when writing.
+    pub quote_style: QuoteStyle,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect {
+            delimiter: ',',
+            quote: '"',
+            escape: Escape::Doubled,
+            comment: None,
+            terminator: LineTerminator::Lf,
+            quote_style: QuoteStyle::Necessary,
+        }
+    }
+}
+
+impl Dialect {
+    /// The RFC 4180 dialect; the same as [`Dialect::default`].
+    pub fn new() -> Self {
+        Dialect::default()
+    }
+
+    /// Tab separated values.
+    pub fn tsv() -> Self {
+        Dialect::default().delimiter('\t')
+    }
+
+    /// Sets the field delimiter.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets the quote character.
+    pub fn quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Sets how quotes are escaped inside quoted fields.
+    pub fn escape(mut self, escape: Escape) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    /// Lines starting with `prefix` are skipped when reading.
+    pub fn comment(mut self, prefix: char) -> Self {
+        self.comment = Some(prefix);
+        self
+    }
+
+    /// Sets the line terminator written after each record.
+    pub fn terminator(mut self, terminator: LineTerminator) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Sets which fields are quoted when writing.
+    pub fn quote_style(mut self, quote_style: QuoteStyle) -> Self {
+        self.quote_style = quote_style;
+        self
+    }
+
+    pub(crate) fn is_escape(&self, c: char) -> bool {
+        matches!(self.escape, Escape::Char(e) if e == c)
+    }
+}
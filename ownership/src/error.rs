@@ -0,0 +1,270 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// A location in the input: 1-based line and column (in characters) and the
+/// 0-based byte offset from the start of the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// 1-based line number.
+    pub line: u64,
+    /// 1-based column, counted in characters.
+    pub column: u64,
+    /// 0-based byte offset from the start of the input.
+    pub byte: u64,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position {
+            line: 1,
+            column: 1,
+            byte: 0,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Everything that can go wrong reading, editing or writing CSV data.
+#[derive(Debug)]
+pub enum CsvError {
+    /// The underlying reader or writer failed.
+    Io(io::Error),
+    /// A quoted field was still open at the end of the input.
+    UnterminatedQuote {
+        /// Where the opening quote is.
+        position: Position,
+    },
+    /// A record had a different number of fields than expected.
+    RaggedRow {
+        /// Where the record starts.
+        position: Position,
+        /// The number of fields other records have.
+        expected: usize,
+        /// The number of fields this record has.
+        found: usize,
+    },
+    /// The input is not valid UTF-8.
+    InvalidUtf8 {
+        /// Where the first invalid byte is.
+        position: Position,
+    },
+    /// A row index is past the end of the data.
+    RowOutOfBounds {
+        /// The requested row.
+        row: usize,
+        /// The number of data rows.
+        rows: usize,
+    },
+    /// A column index is past the widest row.
+    ColumnOutOfBounds {
+        /// The requested column.
+        col: usize,
+        /// The number of columns.
+        columns: usize,
+    },
+    /// No header has this name, or the parser has no headers.
+    UnknownColumn(String),
+    /// A cell had to be a number but was not.
+    NotANumber {
+        /// The data row.
+        row: usize,
+        /// The header, or index, of the column.
+        column: String,
+        /// The cell.
+        value: String,
+    },
+    /// A join was not given one right key column for each left one.
+    KeyMismatch {
+        /// The number of left key columns.
+        left: usize,
+        /// The number of right key columns.
+        right: usize,
+    },
+    /// A filter expression could not be parsed.
+    InvalidExpression {
+        /// The 1-based column, in characters, where the problem is.
+        column: usize,
+        /// What was wrong.
+        message: String,
+    },
+    /// There was nothing to sniff a dialect from.
+    EmptyInput,
+    /// A [`Schema`](crate::Schema) could not be read, or has a pattern that
+    /// is not a valid regular expression.
+    InvalidSchema {
+        /// What was wrong.
+        message: String,
+    },
+    /// JSON input was malformed or was not made of objects.
+    InvalidJson {
+        /// What was wrong, and where.
+        message: String,
+    },
+    /// Data broke a [`Schema`](crate::Schema); the
+    /// [`ValidationReport`](crate::ValidationReport) says how.
+    SchemaViolated {
+        /// The number of violations.
+        violations: usize,
+    },
+    /// A row could not be converted into the requested type.
+    Deserialize {
+        /// The data row being converted.
+        row: usize,
+        /// The header, or index, of the offending column, if known.
+        column: Option<String>,
+        /// What went wrong.
+        message: String,
+    },
+    /// A record could not be converted into a row.
+    Serialize {
+        /// The index of the record being converted.
+        record: usize,
+        /// What went wrong.
+        message: String,
+    },
+    /// An edit in a [`Transaction`](crate::Transaction) failed, so the whole
+    /// transaction was rolled back.
+    Rollback {
+        /// The index of the failed edit among those staged.
+        edit: usize,
+        /// The failed edit.
+        description: String,
+        /// Why it failed.
+        error: Box<CsvError>,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(err) => write!(f, "I/O error: {}", err),
+            CsvError::UnterminatedQuote { position } => {
+                write!(f, "{}: unterminated quote", position)
+            }
+            CsvError::RaggedRow {
+                position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}: expected {} fields, found {}",
+                position, expected, found
+            ),
+            CsvError::InvalidUtf8 { position } => write!(f, "{}: invalid UTF-8", position),
+            CsvError::RowOutOfBounds { row, rows } => {
+                write!(f, "row {} is out of bounds ({} rows)", row, rows)
+            }
+            CsvError::ColumnOutOfBounds { col, columns } => {
+                write!(f, "column {} is out of bounds ({} columns)", col, columns)
+            }
+            CsvError::UnknownColumn(name) => write!(f, "unknown column: {}", name),
+            CsvError::NotANumber { row, column, value } => {
+                write!(
+                    f,
+                    "row {}, column {}: {:?} is not a number",
+                    row, column, value
+                )
+            }
+            CsvError::KeyMismatch { left, right } => write!(
+                f,
+                "join keys do not pair up: {} left, {} right",
+                left, right
+            ),
+            CsvError::InvalidExpression { column, message } => {
+                write!(f, "expression, column {}: {}", column, message)
+            }
+            CsvError::EmptyInput => write!(f, "input is empty"),
+            CsvError::InvalidSchema { message } => write!(f, "invalid schema: {}", message),
+            CsvError::InvalidJson { message } => write!(f, "invalid JSON: {}", message),
+            CsvError::SchemaViolated { violations } => {
+                write!(f, "{} schema violation(s)", violations)
+            }
+            CsvError::Deserialize {
+                row,
+                column: Some(column),
+                message,
+            } => write!(f, "row {}, column {}: {}", row, column, message),
+            CsvError::Deserialize {
+                row,
+                column: None,
+                message,
+            } => write!(f, "row {}: {}", row, message),
+            CsvError::Serialize { record, message } => {
+                write!(f, "record {}: {}", record, message)
+            }
+            CsvError::Rollback {
+                edit,
+                description,
+                error,
+            } => write!(
+                f,
+                "edit {}, {}, failed and everything was rolled back: {}",
+                edit, description, error
+            ),
+        }
+    }
+}
+
+impl Error for CsvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CsvError::Io(err) => Some(err),
+            CsvError::Rollback { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(err: io::Error) -> Self {
+        CsvError::Io(err)
+    }
+}
+
+/// The error serde works with while mapping a single row. It is wrapped in
+/// [`CsvError::Deserialize`] or [`CsvError::Serialize`] once the row is known.
+#[derive(Debug)]
+pub(crate) struct FieldError {
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl FieldError {
+    /// Attributes the error to `column` unless it already names one.
+    pub fn at(mut self, column: usize) -> Self {
+        self.column.get_or_insert(column);
+        self
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for FieldError {}
+
+impl serde::de::Error for FieldError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        FieldError {
+            column: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl serde::ser::Error for FieldError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        FieldError {
+            column: None,
+            message: msg.to_string(),
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::error::CsvError;
+use crate::Row;
+
+/// A row predicate compiled from a small expression language, for
+/// [`CSVParser::filter_expr`](crate::CSVParser::filter_expr) and
+/// `csv filter`.
+///
+/// An expression compares columns and literals with `==`, `!=`, `<`, `<=`,
+/// `>` and `>=`, and combines comparisons with `&&`, `||`, `!` and
+/// parentheses:
+///
+/// ```text
+/// Age > 30 && City != "Paris"
+/// !(`Last Name` == 'Smith') || #0 >= 10
+/// ```
+///
+/// Columns are named by their header, quoted in backticks if the name is not
+/// a plain identifier, or given by index as `#0`, `#1`, ... Strings are quoted
+/// in `"` or `'`. When both sides are numbers the comparison is numeric;
+/// otherwise cells are compared as text. A comparison with a number literal
+/// only holds for cells that are numbers, except `!=`, which holds for every
+/// other cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    root: Node,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Or(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare(Operand, Op, Operand),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Operand {
+    Column(usize),
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Index(usize),
+    Number(f64),
+    Text(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Filter {
+    /// Compiles `expression`, resolving column names against `headers`.
+    pub fn new(expression: &str, headers: Option<&[String]>) -> Result<Self, CsvError> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            tokens,
+            next: 0,
+            headers,
+            end: expression.chars().count() + 1,
+        };
+        let root = parser.or()?;
+        if parser.next < parser.tokens.len() {
+            return Err(parser.error("expected `&&`, `||` or the end of the expression"));
+        }
+        Ok(Filter { root })
+    }
+
+    /// Whether `row` satisfies the expression. Missing cells are empty.
+    pub fn matches(&self, row: &Row) -> bool {
+        self.root.eval(row)
+    }
+}
+
+impl Node {
+    fn eval(&self, row: &Row) -> bool {
+        match self {
+            Node::Or(left, right) => left.eval(row) || right.eval(row),
+            Node::And(left, right) => left.eval(row) && right.eval(row),
+            Node::Not(node) => !node.eval(row),
+            Node::Compare(left, op, right) => compare(left, *op, right, row),
+        }
+    }
+}
+
+fn compare<'a>(left: &'a Operand, op: Op, right: &'a Operand, row: &'a Row) -> bool {
+    let text = |operand: &'a Operand| match operand {
+        Operand::Column(col) => row.get(*col).map(String::as_str).unwrap_or(""),
+        Operand::Number(_) => "",
+        Operand::Text(text) => text.as_str(),
+    };
+    let number = |operand: &Operand| match operand {
+        Operand::Column(col) => row
+            .get(*col)
+            .and_then(|cell| cell.trim().parse::<f64>().ok()),
+        Operand::Number(number) => Some(*number),
+        Operand::Text(_) => None,
+    };
+    let literal_number = matches!(left, Operand::Number(_)) || matches!(right, Operand::Number(_));
+
+    let ordering = match (number(left), number(right)) {
+        (Some(left), Some(right)) => left.partial_cmp(&right),
+        _ if literal_number => None,
+        _ => Some(text(left).cmp(text(right))),
+    };
+    match ordering {
+        Some(ordering) => match op {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+        },
+        None => op == Op::Ne,
+    }
+}
+
+struct Parser<'h> {
+    // Each token with the 1-based column it starts at.
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    headers: Option<&'h [String]>,
+    // The column just past the end of the expression.
+    end: usize,
+}
+
+impl Parser<'_> {
+    fn or(&mut self) -> Result<Node, CsvError> {
+        let mut node = self.and()?;
+        while self.eat(&Token::Or) {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, CsvError> {
+        let mut node = self.unary()?;
+        while self.eat(&Token::And) {
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, CsvError> {
+        if self.eat(&Token::Not) {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::Open) {
+            let node = self.or()?;
+            if !self.eat(&Token::Close) {
+                return Err(self.error("expected `)`"));
+            }
+            return Ok(node);
+        }
+        let left = self.operand()?;
+        let op = match self.tokens.get(self.next) {
+            Some((_, Token::Op(op))) => *op,
+            _ => return Err(self.error("expected a comparison such as `==` or `>`")),
+        };
+        self.next += 1;
+        let right = self.operand()?;
+        Ok(Node::Compare(left, op, right))
+    }
+
+    fn operand(&mut self) -> Result<Operand, CsvError> {
+        let operand = match self.tokens.get(self.next) {
+            Some((_, Token::Name(name))) => {
+                let col = self
+                    .headers
+                    .and_then(|headers| headers.iter().position(|header| header == name))
+                    .ok_or_else(|| CsvError::UnknownColumn(name.clone()))?;
+                Operand::Column(col)
+            }
+            Some((_, Token::Index(col))) => Operand::Column(*col),
+            Some((_, Token::Number(number))) => Operand::Number(*number),
+            Some((_, Token::Text(text))) => Operand::Text(text.clone()),
+            _ => return Err(self.error("expected a column, a number or a string")),
+        };
+        self.next += 1;
+        Ok(operand)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = matches!(self.tokens.get(self.next), Some((_, next)) if next == token);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn error(&self, message: &str) -> CsvError {
+        let column = match self.tokens.get(self.next) {
+            Some((column, _)) => *column,
+            None => self.end,
+        };
+        CsvError::InvalidExpression {
+            column,
+            message: message.to_string(),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<(usize, Token)>, CsvError> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.char_indices().peekable();
+    while let Some(&(offset, c)) = chars.peek() {
+        // Columns are counted in characters, while `chars` yields byte
+        // offsets.
+        let column = expression[..offset].chars().count() + 1;
+        let error = |message: &str| CsvError::InvalidExpression {
+            column,
+            message: message.to_string(),
+        };
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let token = match c {
+            '(' | ')' => {
+                chars.next();
+                if c == '(' {
+                    Token::Open
+                } else {
+                    Token::Close
+                }
+            }
+            '&' | '|' => {
+                chars.next();
+                if chars.next_if(|&(_, next)| next == c).is_none() {
+                    return Err(error(&format!("expected `{}{}`", c, c)));
+                }
+                if c == '&' {
+                    Token::And
+                } else {
+                    Token::Or
+                }
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let equals = chars.next_if(|&(_, next)| next == '=').is_some();
+                match (c, equals) {
+                    ('=', true) => Token::Op(Op::Eq),
+                    ('=', false) => return Err(error("expected `==`")),
+                    ('!', true) => Token::Op(Op::Ne),
+                    ('!', false) => Token::Not,
+                    ('<', true) => Token::Op(Op::Le),
+                    ('<', false) => Token::Op(Op::Lt),
+                    ('>', true) => Token::Op(Op::Ge),
+                    _ => Token::Op(Op::Gt),
+                }
+            }
+            '"' | '\'' => {
+                Token::Text(quoted(&mut chars, c).ok_or_else(|| error("unterminated string"))?)
+            }
+            '`' => {
+                Token::Name(quoted(&mut chars, c).ok_or_else(|| error("unterminated column name"))?)
+            }
+            '#' => {
+                chars.next();
+                let digits = take_while(&mut chars, |c| c.is_ascii_digit());
+                Token::Index(
+                    digits
+                        .parse()
+                        .map_err(|_| error("expected a column index after `#`"))?,
+                )
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let text = take_while(&mut chars, |c| {
+                    c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+')
+                });
+                Token::Number(
+                    text.parse()
+                        .map_err(|_| error(&format!("not a number: {}", text)))?,
+                )
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                Token::Name(take_while(&mut chars, |c| c.is_alphanumeric() || c == '_'))
+            }
+            c => return Err(error(&format!("unexpected `{}`", c))),
+        };
+        tokens.push((column, token));
+    }
+    Ok(tokens)
+}
+
+/// Reads a string opened by `quote`, in which the quote itself may be
+/// escaped with a backslash. Returns `None` if it is never closed.
+fn quoted(chars: &mut Peekable<CharIndices>, quote: char) -> Option<String> {
+    chars.next();
+    let mut text = String::new();
+    loop {
+        let (_, c) = chars.next()?;
+        match c {
+            '\\' => text.push(chars.next()?.1),
+            c if c == quote => return Some(text),
+            c => text.push(c),
+        }
+    }
+}
+
+fn take_while(chars: &mut Peekable<CharIndices>, mut keep: impl FnMut(char) -> bool) -> String {
+    let mut text = String::new();
+    while let Some((_, c)) = chars.next_if(|&(_, c)| keep(c)) {
+        text.push(c);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::row;
+
+    fn headers() -> Row {
+        row(&["Name", "Age", "City", "Last Name"])
+    }
+
+    fn matches(expression: &str, cells: &[&str]) -> bool {
+        Filter::new(expression, Some(&headers()))
+            .unwrap()
+            .matches(&row(cells))
+    }
+
+    fn error_column(expression: &str) -> usize {
+        match Filter::new(expression, Some(&headers())) {
+            Err(CsvError::InvalidExpression { column, .. }) => column,
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn compares_numbers_numerically_and_text_as_text() {
+        assert!(matches("Age > 9", &["Ann", "31"]));
+        assert!(matches("Age == 31.0", &["Ann", "31"]));
+        assert!(!matches("Name > 'b'", &["Ann"]));
+        assert!(matches("#0 <= \"Ann\"", &["Ann"]));
+    }
+
+    #[test]
+    fn only_lets_numbers_match_a_number_literal() {
+        assert!(!matches("Age < 30", &["Ann", "unknown"]));
+        assert!(!matches("Age >= 30", &["Ann"]));
+        assert!(matches("Age != 30", &["Ann", "unknown"]));
+    }
+
+    #[test]
+    fn combines_comparisons_with_precedence() {
+        let expression = "Age > 30 && City != \"Paris\" || !(`Last Name` == 'Smith')";
+        assert!(matches(expression, &["Ann", "31", "Rome", "Smith"]));
+        assert!(!matches(expression, &["Ann", "31", "Paris", "Smith"]));
+        assert!(matches(expression, &["Ann", "20", "Paris", "Jones"]));
+        assert!(!matches("!(Age > 30 || Age < 10)", &["Ann", "5"]));
+    }
+
+    #[test]
+    fn reads_escapes_in_strings() {
+        assert!(matches(r#"Name == "say \"hi\"""#, &[r#"say "hi""#]));
+        assert!(matches(r"City == 'O\'Hare'", &["", "", "O'Hare"]));
+    }
+
+    #[test]
+    fn reports_the_column_of_a_malformed_expression() {
+        assert_eq!(error_column("Age >"), 6);
+        assert_eq!(error_column("Age > 30 & City"), 10);
+        assert_eq!(error_column("Age = 30"), 5);
+        assert_eq!(error_column("(Age > 30"), 10);
+        assert_eq!(error_column("Name == 'Ann"), 9);
+        assert_eq!(error_column("Age > 30 City"), 10);
+        assert_eq!(error_column("Name == 'é' @"), 13);
+    }
+
+    #[test]
+    fn needs_headers_for_column_names() {
+        let err = Filter::new("Salary > 3", Some(&headers())).unwrap_err();
+        assert!(matches!(err, CsvError::UnknownColumn(name) if name == "Salary"));
+        let err = Filter::new("Age > 3", None).unwrap_err();
+        assert!(matches!(err, CsvError::UnknownColumn(_)));
+        assert!(Filter::new("#1 > 3", None).is_ok());
+    }
+}
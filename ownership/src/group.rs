@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::CsvError;
+use crate::parser::{resolve_column, CSVParser};
+use crate::sort::{self, Collation};
+use crate::Row;
+
+/// A summary of one column over each group, for [`GroupBy::agg`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Aggregate {
+    function: Function,
+    column: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Function {
+    Count,
+    Sum,
+    Mean,
+    Min,
+    Max,
+}
+
+/// The number of rows in each group.
+pub fn count() -> Aggregate {
+    Aggregate {
+        function: Function::Count,
+        column: None,
+    }
+}
+
+/// The total of `column`'s numbers in each group.
+pub fn sum(column: &str) -> Aggregate {
+    Aggregate::of(Function::Sum, column)
+}
+
+/// The average of `column`'s numbers in each group.
+pub fn mean(column: &str) -> Aggregate {
+    Aggregate::of(Function::Mean, column)
+}
+
+/// The smallest of `column`'s cells in each group, compared as in
+/// [`Collation::Auto`].
+pub fn min(column: &str) -> Aggregate {
+    Aggregate::of(Function::Min, column)
+}
+
+/// The largest of `column`'s cells in each group, compared as in
+/// [`Collation::Auto`].
+pub fn max(column: &str) -> Aggregate {
+    Aggregate::of(Function::Max, column)
+}
+
+impl Aggregate {
+    fn of(function: Function, column: &str) -> Self {
+        Aggregate {
+            function,
+            column: Some(column.to_string()),
+        }
+    }
+}
+
+/// Headed as in the output of [`GroupBy::agg`]: `count`, `mean(Age)`, ...
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.function {
+            Function::Count => "count",
+            Function::Sum => "sum",
+            Function::Mean => "mean",
+            Function::Min => "min",
+            Function::Max => "max",
+        };
+        match &self.column {
+            Some(column) => write!(f, "{}({})", name, column),
+            None => f.write_str(name),
+        }
+    }
+}
+
+/// A [`CSVParser`]'s rows grouped by the values of one or more columns, as
+/// returned by [`CSVParser::group_by`].
+#[derive(Clone, Debug)]
+pub struct GroupBy<'a> {
+    parser: &'a CSVParser,
+    columns: Vec<String>,
+}
+
+impl<'a> GroupBy<'a> {
+    pub(crate) fn new(parser: &'a CSVParser, columns: Vec<String>) -> Self {
+        GroupBy { parser, columns }
+    }
+
+    /// Summarizes each group in a row of a new parser: the grouping
+    /// columns' values followed by one cell per aggregate.
+    ///
+    /// Groups appear in the order their first row does. Empty cells are
+    /// ignored by everything but [`count`]; [`sum`] and [`mean`] fail on any
+    /// other cell that is not a number.
+    pub fn agg<I>(self, aggregates: I) -> Result<CSVParser, CsvError>
+    where
+        I: IntoIterator<Item = Aggregate>,
+    {
+        let headers = self.parser.headers();
+        let data = self.parser.rows();
+        let columns = self.parser.column_count();
+        let keys = self
+            .columns
+            .iter()
+            .map(|column| resolve_column(headers, column, columns))
+            .collect::<Result<Vec<_>, _>>()?;
+        let aggregates: Vec<Aggregate> = aggregates.into_iter().collect();
+        let targets = aggregates
+            .iter()
+            .map(|aggregate| match &aggregate.column {
+                Some(column) => {
+                    let col = resolve_column(headers, column, columns)?;
+                    Ok(Some((col, sort::infer(data, col))))
+                }
+                None => Ok(None),
+            })
+            .collect::<Result<Vec<_>, CsvError>>()?;
+
+        // Row indices of each group, in order of first appearance.
+        let mut groups: Vec<(Row, Vec<usize>)> = Vec::new();
+        let mut index: HashMap<Row, usize> = HashMap::new();
+        for (row_index, row) in data.iter().enumerate() {
+            let key: Row = keys
+                .iter()
+                .map(|&col| row.get(col).cloned().unwrap_or_default())
+                .collect();
+            let group = *index.entry(key.clone()).or_insert_with(|| {
+                groups.push((key, Vec::new()));
+                groups.len() - 1
+            });
+            groups[group].1.push(row_index);
+        }
+
+        let mut out = Vec::with_capacity(groups.len());
+        for (mut key, rows) in groups {
+            for (aggregate, target) in aggregates.iter().zip(&targets) {
+                let cell = match target {
+                    Some((col, collation)) => {
+                        summarize(aggregate.function, data, &rows, *col, *collation, headers)?
+                    }
+                    None => rows.len().to_string(),
+                };
+                key.push(cell);
+            }
+            out.push(key);
+        }
+
+        let mut names: Row = keys
+            .iter()
+            .zip(&self.columns)
+            .map(|(&col, column)| {
+                headers
+                    .and_then(|headers| headers.get(col))
+                    .unwrap_or(column)
+                    .clone()
+            })
+            .collect();
+        names.extend(aggregates.iter().map(|aggregate| aggregate.to_string()));
+        Ok(CSVParser::from_parts(
+            Some(names),
+            out,
+            self.parser.dialect(),
+        ))
+    }
+}
+
+fn summarize(
+    function: Function,
+    data: &[Row],
+    rows: &[usize],
+    col: usize,
+    collation: Collation,
+    headers: Option<&Row>,
+) -> Result<String, CsvError> {
+    let cells = rows
+        .iter()
+        .filter_map(|&row| Some((row, data[row].get(col)?.as_str())))
+        .filter(|(_, cell)| !cell.trim().is_empty());
+    Ok(match function {
+        Function::Count => rows.len().to_string(),
+        Function::Min => cells
+            .map(|(_, cell)| cell)
+            .min_by(|a, b| sort::compare_cells(a, b, collation))
+            .unwrap_or_default()
+            .to_string(),
+        Function::Max => cells
+            .map(|(_, cell)| cell)
+            .max_by(|a, b| sort::compare_cells(a, b, collation))
+            .unwrap_or_default()
+            .to_string(),
+        Function::Sum | Function::Mean => {
+            let mut total = 0.0;
+            let mut count = 0;
+            for (row, cell) in cells {
+                total += cell
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| CsvError::NotANumber {
+                        row,
+                        column: match headers.and_then(|headers| headers.get(col)) {
+                            Some(name) => name.clone(),
+                            None => col.to_string(),
+                        },
+                        value: cell.to_string(),
+                    })?;
+                count += 1;
+            }
+            match function {
+                Function::Sum => total.to_string(),
+                _ if count == 0 => String::new(),
+                _ => (total / count as f64).to_string(),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{contents, parser, row, rows};
+
+    const STAFF: &str = "Team,Name,Age,Start\n\
+                         red,Ann,31,2020-03-01\n\
+                         blue,Bob,25,2019-11-15\n\
+                         red,Cid,40,\n\
+                         blue,Dan,,2021-01-10\n";
+
+    #[test]
+    fn summarizes_each_group_in_order_of_first_appearance() {
+        let parser = parser(STAFF);
+        let groups = parser
+            .group_by("Team")
+            .agg([count(), sum("Age"), mean("Age")])
+            .unwrap();
+        let (headers, data) = contents(&groups);
+        assert_eq!(
+            headers.unwrap(),
+            row(&["Team", "count", "sum(Age)", "mean(Age)"])
+        );
+        assert_eq!(
+            data,
+            rows(&[&["red", "2", "71", "35.5"], &["blue", "2", "25", "25"]])
+        );
+    }
+
+    #[test]
+    fn compares_min_and_max_to_suit_the_column() {
+        let parser = parser(STAFF);
+        let groups = parser
+            .group_by("Team")
+            .agg([min("Age"), max("Start")])
+            .unwrap();
+        assert_eq!(
+            contents(&groups).1,
+            rows(&[&["red", "31", "2020-03-01"], &["blue", "25", "2021-01-10"]])
+        );
+    }
+
+    #[test]
+    fn groups_by_several_columns_and_indices() {
+        let parser = parser("a,b\nx,1\nx,2\nx,1\n");
+        let groups = parser.group_by_columns(&["0", "b"]).agg([count()]).unwrap();
+        assert_eq!(groups.headers(), Some(&row(&["a", "b", "count"])));
+        assert_eq!(
+            contents(&groups).1,
+            rows(&[&["x", "1", "2"], &["x", "2", "1"]])
+        );
+    }
+
+    #[test]
+    fn names_the_cell_that_is_not_a_number() {
+        let parser = parser("Team,Age\nred,31\nred,old\n");
+        let err = parser.group_by("Team").agg([sum("Age")]).unwrap_err();
+        let CsvError::NotANumber { row, column, value } = err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert_eq!((row, column.as_str(), value.as_str()), (1, "Age", "old"));
+    }
+
+    #[test]
+    fn rejects_unknown_columns() {
+        let parser = parser(STAFF);
+        let err = parser.group_by("City").agg([count()]).unwrap_err();
+        assert!(matches!(err, CsvError::UnknownColumn(name) if name == "City"));
+        let err = parser.group_by("Team").agg([sum("9")]).unwrap_err();
+        assert!(matches!(
+            err,
+            CsvError::ColumnOutOfBounds { col: 9, columns: 4 }
+        ));
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt;
+
+use crate::Row;
+
+/// One change made to a [`CSVParser`](crate::CSVParser), with enough of the
+/// old contents to take it back.
+///
+/// Rows are data row indices, as everywhere on the parser. Where an edit
+/// touches the header row too, its part comes first in the per-row fields.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Edit {
+    /// A cell was overwritten.
+    UpdateCell {
+        /// The data row.
+        row: usize,
+        /// The column.
+        col: usize,
+        /// The value before the edit.
+        old: String,
+        /// The value after the edit.
+        new: String,
+    },
+    /// A row was inserted or appended.
+    InsertRow {
+        /// Where the row now is.
+        row: usize,
+        /// The inserted row.
+        values: Row,
+    },
+    /// A row was removed.
+    RemoveRow {
+        /// Where the row was.
+        row: usize,
+        /// The removed row.
+        values: Row,
+    },
+    /// A column was inserted.
+    InsertColumn {
+        /// Where the column now is.
+        col: usize,
+        /// The new cell of the header row and of each data row.
+        values: Vec<String>,
+        /// The length of the header row and of each data row before the
+        /// edit, since short rows were padded to reach the column.
+        lengths: Vec<usize>,
+    },
+    /// A column was removed.
+    RemoveColumn {
+        /// Where the column was.
+        col: usize,
+        /// The removed cell of the header row and of each data row, or
+        /// `None` where the row was too short to have one.
+        values: Vec<Option<String>>,
+    },
+    /// Two columns were swapped.
+    SwapColumns {
+        /// One of the columns.
+        a: usize,
+        /// The other column.
+        b: usize,
+        /// The length of the header row and of each data row before the
+        /// edit, since short rows were padded to reach both columns.
+        lengths: Vec<usize>,
+    },
+    /// The rows were reordered.
+    Sort {
+        /// For each row after the edit, where it was before.
+        order: Vec<usize>,
+    },
+    /// A column header was renamed.
+    RenameColumn {
+        /// The column.
+        col: usize,
+        /// The header before the edit.
+        old: String,
+        /// The header after the edit.
+        new: String,
+    },
+}
+
+impl Edit {
+    /// Takes the edit back out of `headers` and `data`.
+    pub(crate) fn revert(&self, headers: &mut Option<Row>, data: &mut Vec<Row>) {
+        match self {
+            Edit::UpdateCell { row, col, old, .. } => data[*row][*col] = old.clone(),
+            Edit::InsertRow { row, .. } => {
+                data.remove(*row);
+            }
+            Edit::RemoveRow { row, values } => data.insert(*row, values.clone()),
+            Edit::InsertColumn { col, lengths, .. } => {
+                for (row, &len) in rows(headers, data).zip(lengths) {
+                    row.remove(*col);
+                    row.truncate(len);
+                }
+            }
+            Edit::RemoveColumn { col, values } => {
+                for (row, value) in rows(headers, data).zip(values) {
+                    if let Some(value) = value {
+                        row.insert(*col, value.clone());
+                    }
+                }
+            }
+            Edit::SwapColumns { a, b, lengths } => {
+                for (row, &len) in rows(headers, data).zip(lengths) {
+                    row.swap(*a, *b);
+                    row.truncate(len);
+                }
+            }
+            Edit::Sort { order } => {
+                let mut restored = vec![None; data.len()];
+                for (row, &index) in data.drain(..).zip(order) {
+                    restored[index] = Some(row);
+                }
+                data.extend(restored.into_iter().flatten());
+            }
+            Edit::RenameColumn { col, old, .. } => {
+                if let Some(headers) = headers {
+                    headers[*col] = old.clone();
+                }
+            }
+        }
+    }
+
+    /// Makes the edit again after it was reverted.
+    pub(crate) fn apply(&self, headers: &mut Option<Row>, data: &mut Vec<Row>) {
+        match self {
+            Edit::UpdateCell { row, col, new, .. } => data[*row][*col] = new.clone(),
+            Edit::InsertRow { row, values } => data.insert(*row, values.clone()),
+            Edit::RemoveRow { row, .. } => {
+                data.remove(*row);
+            }
+            Edit::InsertColumn { col, values, .. } => {
+                for (row, value) in rows(headers, data).zip(values) {
+                    pad(row, *col);
+                    row.insert(*col, value.clone());
+                }
+            }
+            Edit::RemoveColumn { col, .. } => {
+                for row in rows(headers, data) {
+                    if *col < row.len() {
+                        row.remove(*col);
+                    }
+                }
+            }
+            Edit::SwapColumns { a, b, .. } => {
+                for row in rows(headers, data) {
+                    pad(row, a.max(b) + 1);
+                    row.swap(*a, *b);
+                }
+            }
+            Edit::Sort { order } => {
+                let mut rows: Vec<Option<Row>> = data.drain(..).map(Some).collect();
+                data.extend(order.iter().filter_map(|&index| rows[index].take()));
+            }
+            Edit::RenameColumn { col, new, .. } => {
+                if let Some(headers) = headers {
+                    headers[*col] = new.clone();
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edit::UpdateCell { row, col, old, new } => {
+                write!(f, "set ({}, {}) from {:?} to {:?}", row, col, old, new)
+            }
+            Edit::InsertRow { row, values } => write!(f, "inserted row {}: {:?}", row, values),
+            Edit::RemoveRow { row, values } => write!(f, "removed row {}: {:?}", row, values),
+            Edit::InsertColumn { col, .. } => write!(f, "inserted column {}", col),
+            Edit::RemoveColumn { col, .. } => write!(f, "removed column {}", col),
+            Edit::SwapColumns { a, b, .. } => write!(f, "swapped columns {} and {}", a, b),
+            Edit::Sort { .. } => write!(f, "sorted the rows"),
+            Edit::RenameColumn { col, old, new } => {
+                write!(f, "renamed column {} from {:?} to {:?}", col, old, new)
+            }
+        }
+    }
+}
+
+/// The header row, if any, followed by every data row.
+fn rows<'a>(
+    headers: &'a mut Option<Row>,
+    data: &'a mut [Row],
+) -> impl Iterator<Item = &'a mut Row> {
+    headers.iter_mut().chain(data)
+}
+
+/// Appends empty cells to `row` until it has at least `len` of them.
+fn pad(row: &mut Row, len: usize) {
+    if row.len() < len {
+        row.resize(len, String::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{contents, parser, row, rows};
+    use crate::{CSVParser, Order, Row};
+
+    /// Headers and rows of uneven lengths, the first data row short.
+    const UNEVEN: &str = "name,age,city\nb,2\na,10,x\nc\n";
+
+    /// Makes an edit, then checks that undo restores the original contents
+    /// and redo the edited ones.
+    fn round_trip<F>(edit: F) -> (Option<Row>, Vec<Row>)
+    where
+        F: FnOnce(&mut CSVParser),
+    {
+        let mut parser = parser(UNEVEN);
+        let before = contents(&parser);
+        edit(&mut parser);
+        let after = contents(&parser);
+        assert!(parser.undo().is_some());
+        assert_eq!(contents(&parser), before);
+        assert!(parser.redo().is_some());
+        assert_eq!(contents(&parser), after);
+        assert!(parser.redo().is_none());
+        after
+    }
+
+    #[test]
+    fn inserting_a_column_past_short_rows_is_undone() {
+        let (headers, data) = round_trip(|parser| {
+            parser
+                .insert_column(3, "n", |row| row.len().to_string())
+                .unwrap()
+        });
+        assert_eq!(headers.unwrap(), row(&["name", "age", "city", "n"]));
+        assert_eq!(data[0], row(&["b", "2", "", "2"]));
+        assert_eq!(data[2], row(&["c", "", "", "1"]));
+    }
+
+    #[test]
+    fn removing_a_column_short_rows_lack_is_undone() {
+        let (headers, data) = round_trip(|parser| parser.remove_column(1).unwrap());
+        assert_eq!(headers.unwrap(), row(&["name", "city"]));
+        assert_eq!(data, rows(&[&["b"], &["a", "x"], &["c"]]));
+    }
+
+    #[test]
+    fn swapping_columns_short_rows_lack_is_undone() {
+        let (_, data) = round_trip(|parser| parser.swap_columns(0, 2).unwrap());
+        assert_eq!(data[0], row(&["", "2", "b"]));
+        assert_eq!(data[2], row(&["", "", "c"]));
+    }
+
+    #[test]
+    fn sorting_is_undone() {
+        let (_, data) =
+            round_trip(|parser| parser.sort_by_columns(&[("age", Order::Desc)]).unwrap());
+        let names: Vec<&str> = data.iter().map(|row| row[0].as_str()).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn row_and_cell_edits_are_undone() {
+        round_trip(|parser| parser.update_cell(1, 2, "y").unwrap());
+        round_trip(|parser| parser.insert_row(1, row(&["d", "4"])).unwrap());
+        round_trip(|parser| {
+            parser.remove_row(0).unwrap();
+        });
+        round_trip(|parser| parser.rename_column("age", "years").unwrap());
+    }
+
+    #[test]
+    fn edits_are_undone_newest_first() {
+        let mut parser = parser(UNEVEN);
+        let before = contents(&parser);
+        parser.swap_columns(1, 2).unwrap();
+        parser.remove_column(0).unwrap();
+        parser.update_cell(1, 0, "y").unwrap();
+        parser.sort_by_columns(&[("1", Order::Asc)]).unwrap();
+        let after = contents(&parser);
+        assert_eq!(parser.history().len(), 4);
+        while parser.undo().is_some() {}
+        assert_eq!(contents(&parser), before);
+        while parser.redo().is_some() {}
+        assert_eq!(contents(&parser), after);
+    }
+
+    #[test]
+    fn a_new_edit_clears_what_can_be_redone() {
+        let mut parser = parser(UNEVEN);
+        parser.remove_row(0).unwrap();
+        parser.undo();
+        parser.update_cell(0, 0, "z").unwrap();
+        assert!(parser.redo().is_none());
+        assert_eq!(parser.history().len(), 1);
+    }
+}
@@ -0,0 +1,427 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use crate::error::CsvError;
+use crate::parser::{resolve_column, CSVParser};
+use crate::reader::CsvReader;
+use crate::writer::CsvWriter;
+use crate::Row;
+
+/// Which rows a join keeps besides those whose keys match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JoinKind {
+    /// Only rows with a match on the other side.
+    #[default]
+    Inner,
+    /// Every left row, matched or not.
+    Left,
+    /// Every right row, matched or not.
+    Right,
+    /// Every row from both sides.
+    Full,
+}
+
+/// How two tables are joined, for [`CSVParser::join`] and [`hash_join`].
+///
+/// Rows match when all their key cells are equal. A row with an empty key
+/// cell never matches. The output has every left column, then the right
+/// columns other than its keys; a right-only row fills the left key columns
+/// with its own keys.
+///
+/// The left columns are as wide as the longest left row or header row, and
+/// shorter left rows are padded so the right columns line up. [`hash_join`]
+/// cannot look ahead, so it takes the width of the left header row, or else
+/// of the first left row, and fails with [`CsvError::RaggedRow`] on a longer
+/// one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JoinOptions {
+    /// Which rows are kept.
+    pub kind: JoinKind,
+    /// The left key columns, headers or indices.
+    pub left_on: Vec<String>,
+    /// The right key columns, headers or indices, in the same order.
+    pub right_on: Vec<String>,
+    /// Appended to left and right headers that would otherwise be the same.
+    pub suffixes: (String, String),
+}
+
+impl JoinOptions {
+    /// An inner join on columns that have the same header, or index, on both
+    /// sides, suffixing conflicting headers with `_left` and `_right`.
+    pub fn new(on: &[&str]) -> Self {
+        let on: Vec<String> = on.iter().map(|column| column.to_string()).collect();
+        JoinOptions {
+            kind: JoinKind::Inner,
+            left_on: on.clone(),
+            right_on: on,
+            suffixes: ("_left".to_string(), "_right".to_string()),
+        }
+    }
+
+    /// Sets which rows are kept.
+    pub fn kind(mut self, kind: JoinKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Sets the right key columns, where they differ from the left ones.
+    pub fn right_on(mut self, on: &[&str]) -> Self {
+        self.right_on = on.iter().map(|column| column.to_string()).collect();
+        self
+    }
+
+    /// Sets what is appended to conflicting left and right headers.
+    pub fn suffixes(mut self, left: &str, right: &str) -> Self {
+        self.suffixes = (left.to_string(), right.to_string());
+        self
+    }
+}
+
+/// Joins rows streamed from `left` to `right`, which is held in memory, and
+/// writes the result to `out` as it goes.
+///
+/// Only `right` and a hash index over its keys are kept in memory, so `left`
+/// can be a file of any size; make the smaller file the right one. Unmatched
+/// right rows of a right or full join are written last. `left` is borrowed
+/// so that records it set aside can be taken from it afterwards.
+pub fn hash_join<R: Read, W: Write>(
+    left: &mut CsvReader<R>,
+    right: &CSVParser,
+    options: &JoinOptions,
+    out: &mut CsvWriter<W>,
+) -> Result<(), CsvError> {
+    let left_headers = left.headers()?.cloned();
+    let mut row = Row::new();
+    let mut more = left.read_record(&mut row)?;
+    let width = left_headers
+        .as_ref()
+        .map_or(row.len(), |headers| headers.len());
+    let mut join = HashJoin::new(left_headers.as_ref(), width, right, options)?;
+    if let Some(headers) = join.headers(left_headers.as_ref(), options) {
+        out.write_record(&headers)?;
+    }
+    let mut emit = |row: Row| out.write_record(&row);
+    while more {
+        if row.len() > width {
+            return Err(CsvError::RaggedRow {
+                position: CsvReader::position(left),
+                expected: width,
+                found: row.len(),
+            });
+        }
+        join.probe(&row, &mut emit)?;
+        more = left.read_record(&mut row)?;
+    }
+    join.finish(&mut emit)?;
+    out.flush()
+}
+
+/// Joins two parsers held in memory; see [`CSVParser::join`].
+pub(crate) fn join(
+    left: &CSVParser,
+    right: &CSVParser,
+    options: &JoinOptions,
+) -> Result<CSVParser, CsvError> {
+    let mut join = HashJoin::new(left.headers(), left.column_count(), right, options)?;
+    let headers = join.headers(left.headers(), options);
+    let mut rows = Vec::new();
+    let mut emit = |row: Row| {
+        rows.push(row);
+        Ok(())
+    };
+    for row in left.rows() {
+        join.probe(row, &mut emit)?;
+    }
+    join.finish(&mut emit)?;
+    Ok(CSVParser::from_parts(headers, rows, left.dialect()))
+}
+
+struct HashJoin<'r> {
+    kind: JoinKind,
+    left_keys: Vec<usize>,
+    right_keys: Vec<usize>,
+    // Right columns that are not keys, in order.
+    right_rest: Vec<usize>,
+    right: &'r [Row],
+    right_headers: Option<&'r Row>,
+    index: HashMap<Vec<&'r str>, Vec<usize>>,
+    matched: Vec<bool>,
+    // How many cells every output row has before the right columns. No
+    // left row is longer.
+    left_width: usize,
+}
+
+impl<'r> HashJoin<'r> {
+    fn new(
+        left_headers: Option<&Row>,
+        left_width: usize,
+        right: &'r CSVParser,
+        options: &JoinOptions,
+    ) -> Result<Self, CsvError> {
+        if options.left_on.len() != options.right_on.len() || options.left_on.is_empty() {
+            return Err(CsvError::KeyMismatch {
+                left: options.left_on.len(),
+                right: options.right_on.len(),
+            });
+        }
+        let left_keys = options
+            .left_on
+            .iter()
+            .map(|column| resolve_column(left_headers, column, left_width))
+            .collect::<Result<Vec<_>, _>>()?;
+        let right_keys = options
+            .right_on
+            .iter()
+            .map(|column| resolve_column(right.headers(), column, right.column_count()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let right_rest = (0..right.column_count())
+            .filter(|col| !right_keys.contains(col))
+            .collect();
+
+        let mut index: HashMap<Vec<&str>, Vec<usize>> = HashMap::new();
+        for (row_index, row) in right.rows().iter().enumerate() {
+            if let Some(key) = key(row, &right_keys) {
+                index.entry(key).or_default().push(row_index);
+            }
+        }
+
+        Ok(HashJoin {
+            kind: options.kind,
+            left_width,
+            left_keys,
+            right_keys,
+            right_rest,
+            right: right.rows(),
+            right_headers: right.headers(),
+            index,
+            matched: vec![false; right.len()],
+        })
+    }
+
+    /// The output header row, if both sides have one.
+    fn headers(&self, left: Option<&Row>, options: &JoinOptions) -> Option<Row> {
+        let (left, right) = (left?, self.right_headers?);
+        let right_names: Vec<&String> = self
+            .right_rest
+            .iter()
+            .filter_map(|&col| right.get(col))
+            .collect();
+        let (left_suffix, right_suffix) = &options.suffixes;
+        let mut headers: Row = left
+            .iter()
+            .enumerate()
+            .map(|(col, name)| {
+                if !self.left_keys.contains(&col) && right_names.contains(&name) {
+                    format!("{}{}", name, left_suffix)
+                } else {
+                    name.clone()
+                }
+            })
+            .collect();
+        headers.extend(right_names.iter().map(|name| {
+            if left.contains(name) {
+                format!("{}{}", name, right_suffix)
+            } else {
+                name.to_string()
+            }
+        }));
+        Some(headers)
+    }
+
+    fn probe<F>(&mut self, left: &Row, emit: &mut F) -> Result<(), CsvError>
+    where
+        F: FnMut(Row) -> Result<(), CsvError>,
+    {
+        let matches = key(left, &self.left_keys).and_then(|key| self.index.get(&key));
+        match matches {
+            Some(matches) => {
+                for &right_index in matches {
+                    self.matched[right_index] = true;
+                    emit(self.combine(Some(left), Some(&self.right[right_index])))?;
+                }
+            }
+            None if matches!(self.kind, JoinKind::Left | JoinKind::Full) => {
+                emit(self.combine(Some(left), None))?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn finish<F>(self, emit: &mut F) -> Result<(), CsvError>
+    where
+        F: FnMut(Row) -> Result<(), CsvError>,
+    {
+        if matches!(self.kind, JoinKind::Right | JoinKind::Full) {
+            for (row, _) in self
+                .right
+                .iter()
+                .zip(&self.matched)
+                .filter(|(_, &matched)| !matched)
+            {
+                emit(self.combine(None, Some(row)))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn combine(&self, left: Option<&Row>, right: Option<&Row>) -> Row {
+        let mut row = left.cloned().unwrap_or_default();
+        row.resize(self.left_width, String::new());
+        if let (None, Some(right)) = (left, right) {
+            for (&left_col, &right_col) in self.left_keys.iter().zip(&self.right_keys) {
+                row[left_col] = right.get(right_col).cloned().unwrap_or_default();
+            }
+        }
+        row.extend(self.right_rest.iter().map(|&col| {
+            right
+                .and_then(|right| right.get(col))
+                .cloned()
+                .unwrap_or_default()
+        }));
+        row
+    }
+}
+
+/// The key cells of `row`, or `None` if any is empty or missing.
+fn key<'a>(row: &'a Row, columns: &[usize]) -> Option<Vec<&'a str>> {
+    columns
+        .iter()
+        .map(|&col| {
+            row.get(col)
+                .map(String::as_str)
+                .filter(|cell| !cell.is_empty())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{contents, parser, row, rows};
+
+    const PEOPLE: &str = "id,name,city\n1,Ann,Rome\n2,Bob,Oslo\n3,Cid,\n,Dan,Rome\n";
+    const ORDERS: &str = "id,item,city\n2,pen,Oslo\n1,ink,Rome\n1,pad,Rome\n4,cap,Lima\n,mug,\n";
+
+    fn joined(kind: JoinKind) -> (Option<Row>, Vec<Row>) {
+        let options = JoinOptions::new(&["id"]).kind(kind);
+        contents(&parser(PEOPLE).join(&parser(ORDERS), &options).unwrap())
+    }
+
+    #[test]
+    fn keeps_only_matches_in_an_inner_join() {
+        let (headers, data) = joined(JoinKind::Inner);
+        assert_eq!(
+            headers.unwrap(),
+            row(&["id", "name", "city_left", "item", "city_right"])
+        );
+        assert_eq!(
+            data,
+            rows(&[
+                &["1", "Ann", "Rome", "ink", "Rome"],
+                &["1", "Ann", "Rome", "pad", "Rome"],
+                &["2", "Bob", "Oslo", "pen", "Oslo"],
+            ])
+        );
+    }
+
+    #[test]
+    fn keeps_unmatched_left_rows_in_a_left_join() {
+        let (_, data) = joined(JoinKind::Left);
+        assert_eq!(data.len(), 5);
+        assert_eq!(data[3], row(&["3", "Cid", "", "", ""]));
+        // An empty key never matches, not even another empty key.
+        assert_eq!(data[4], row(&["", "Dan", "Rome", "", ""]));
+    }
+
+    #[test]
+    fn fills_left_keys_from_unmatched_right_rows() {
+        let (_, data) = joined(JoinKind::Right);
+        assert_eq!(
+            &data[3..],
+            rows(&[&["4", "", "", "cap", "Lima"], &["", "", "", "mug", ""]])
+        );
+        let (_, data) = joined(JoinKind::Full);
+        assert_eq!(data.len(), 7);
+        assert_eq!(data[5], row(&["4", "", "", "cap", "Lima"]));
+    }
+
+    #[test]
+    fn joins_on_differently_named_keys_with_custom_suffixes() {
+        let right = parser("person,city\nAnn,Paris\n");
+        let options = JoinOptions::new(&["name"])
+            .right_on(&["person"])
+            .suffixes(".a", ".b");
+        let out = parser(PEOPLE).join(&right, &options).unwrap();
+        let (headers, data) = contents(&out);
+        assert_eq!(headers.unwrap(), row(&["id", "name", "city.a", "city.b"]));
+        assert_eq!(data, rows(&[&["1", "Ann", "Rome", "Paris"]]));
+    }
+
+    #[test]
+    fn widens_to_the_longest_left_row() {
+        let left = parser("id,name\n1,Ann,extra\n2\n");
+        let right = parser("id,item\n1,ink\n2,pen\n");
+        let out = left.join(&right, &JoinOptions::new(&["id"])).unwrap();
+        assert_eq!(
+            contents(&out).1,
+            rows(&[&["1", "Ann", "extra", "ink"], &["2", "", "", "pen"]])
+        );
+    }
+
+    #[test]
+    fn rejects_keys_that_do_not_pair_up() {
+        let options = JoinOptions::new(&["id"]).right_on(&["id", "city"]);
+        let err = parser(PEOPLE).join(&parser(ORDERS), &options).unwrap_err();
+        assert!(matches!(err, CsvError::KeyMismatch { left: 1, right: 2 }));
+        let options = JoinOptions::new(&["7"]);
+        let err = parser(PEOPLE).join(&parser(ORDERS), &options).unwrap_err();
+        assert!(matches!(err, CsvError::ColumnOutOfBounds { col: 7, .. }));
+    }
+
+    fn stream(left: &str, kind: JoinKind) -> Result<String, CsvError> {
+        let mut reader = CsvReader::new(left.as_bytes()).has_headers(true);
+        let mut out = Vec::new();
+        let options = JoinOptions::new(&["id"]).kind(kind);
+        hash_join(
+            &mut reader,
+            &parser(ORDERS),
+            &options,
+            &mut CsvWriter::new(&mut out),
+        )?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn streams_the_same_rows_as_the_in_memory_join() {
+        assert_eq!(
+            stream(PEOPLE, JoinKind::Full).unwrap(),
+            "id,name,city_left,item,city_right\n\
+             1,Ann,Rome,ink,Rome\n\
+             1,Ann,Rome,pad,Rome\n\
+             2,Bob,Oslo,pen,Oslo\n\
+             3,Cid,,,\n\
+             ,Dan,Rome,,\n\
+             4,,,cap,Lima\n\
+             ,,,mug,\n"
+        );
+    }
+
+    #[test]
+    fn fails_on_a_streamed_left_row_longer_than_the_header() {
+        let err = stream("id,name\n1,Ann\n2,Bob,extra\n", JoinKind::Inner).unwrap_err();
+        let CsvError::RaggedRow {
+            position,
+            expected,
+            found,
+        } = err
+        else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert_eq!((position.line, expected, found), (3, 2, 3));
+        assert_eq!(
+            stream("id,name\n1\n", JoinKind::Inner).unwrap(),
+            "id,name,item,city\n1,,ink,Rome\n1,,pad,Rome\n"
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read};
+
+use serde_json::{Map, Number, Value as Json};
+
+use crate::error::CsvError;
+use crate::value::{ColumnType, Value};
+use crate::Row;
+
+/// Renders each row as an object keyed by header, or by column index
+/// without headers: a JSON array of them, one per line, or JSON Lines.
+///
+/// A cell in a column of known type is written as that type, and otherwise
+/// as a string. Cells missing from short rows are null, and repeated header
+/// names are made unique as in [`unique_keys`].
+pub(crate) fn to_json(
+    headers: Option<&Row>,
+    data: &[Row],
+    types: Option<&[ColumnType]>,
+    lines: bool,
+) -> String {
+    let width = headers
+        .into_iter()
+        .chain(data)
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0);
+    let keys: Vec<String> = unique_keys(headers, width)
+        .into_iter()
+        .map(|key| Json::String(key).to_string())
+        .collect();
+
+    let objects = data.iter().map(|row| {
+        let mut object = String::from("{");
+        for (col, key) in keys.iter().enumerate() {
+            if col > 0 {
+                object.push(',');
+            }
+            let column_type = types.and_then(|types| types.get(col)).copied();
+            object.push_str(key);
+            object.push(':');
+            object.push_str(&json_value(row.get(col), column_type).to_string());
+        }
+        object.push('}');
+        object
+    });
+
+    let mut out = String::new();
+    if lines {
+        for object in objects {
+            out.push_str(&object);
+            out.push('\n');
+        }
+    } else {
+        out.push('[');
+        for (index, object) in objects.enumerate() {
+            out.push_str(if index == 0 { "\n  " } else { ",\n  " });
+            out.push_str(&object);
+        }
+        out.push_str(if data.is_empty() { "]\n" } else { "\n]\n" });
+    }
+    out
+}
+
+/// The key for each of `width` columns: its header, or its index where it
+/// has none. A key already taken gets the first free suffix of `_2`, `_3`,
+/// ..., so an object never repeats a key.
+fn unique_keys(headers: Option<&Row>, width: usize) -> Vec<String> {
+    let names: Vec<String> = (0..width)
+        .map(|col| match headers.and_then(|headers| headers.get(col)) {
+            Some(name) => name.clone(),
+            None => col.to_string(),
+        })
+        .collect();
+    // Every name as written is reserved first, so a renamed key cannot take
+    // one that a later column already has.
+    let mut taken: HashSet<String> = names.iter().cloned().collect();
+    let mut seen = HashSet::new();
+    names
+        .into_iter()
+        .map(|name| {
+            if seen.insert(name.clone()) {
+                return name;
+            }
+            let key = (2..)
+                .map(|n| format!("{}_{}", name, n))
+                .find(|key| !taken.contains(key))
+                .unwrap();
+            taken.insert(key.clone());
+            key
+        })
+        .collect()
+}
+
+fn json_value(cell: Option<&String>, column_type: Option<ColumnType>) -> Json {
+    let Some(cell) = cell else {
+        return Json::Null;
+    };
+    let value = match column_type {
+        Some(column_type) => {
+            Value::parse_as(cell, column_type).unwrap_or_else(|| Value::Text(cell.clone()))
+        }
+        None => Value::Text(cell.clone()),
+    };
+    match value {
+        Value::Null => Json::Null,
+        Value::Integer(number) => Json::from(number),
+        Value::Float(number) => Number::from_f64(number).map_or(Json::Null, Json::Number),
+        Value::Boolean(boolean) => Json::Bool(boolean),
+        Value::Date(date) => Json::String(date.to_string()),
+        Value::Text(text) => Json::String(text),
+    }
+}
+
+/// Reads a JSON array of objects into a header row and data rows.
+pub(crate) fn from_json<R: Read>(source: R) -> Result<(Row, Vec<Row>), CsvError> {
+    let records: Vec<Json> = serde_json::from_reader(source).map_err(invalid_json)?;
+    let mut table = Table::default();
+    for (index, record) in records.into_iter().enumerate() {
+        table
+            .push(record)
+            .map_err(|message| CsvError::InvalidJson {
+                message: format!("record {}: {}", index, message),
+            })?;
+    }
+    Ok(table.finish())
+}
+
+/// Reads JSON Lines, one object per line, into a header row and data rows.
+/// Blank lines are skipped.
+pub(crate) fn from_jsonl<R: Read>(source: R) -> Result<(Row, Vec<Row>), CsvError> {
+    let mut table = Table::default();
+    for (index, line) in BufReader::new(source).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let located = |message: String| CsvError::InvalidJson {
+            message: format!("line {}: {}", index + 1, message),
+        };
+        let record = serde_json::from_str(&line).map_err(|err| located(err.to_string()))?;
+        table.push(record).map_err(located)?;
+    }
+    Ok(table.finish())
+}
+
+fn invalid_json(err: serde_json::Error) -> CsvError {
+    if err.is_io() {
+        CsvError::Io(err.into())
+    } else {
+        CsvError::InvalidJson {
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Rows built from objects, with a header row that is the union of their
+/// keys in the order they are first seen.
+#[derive(Default)]
+struct Table {
+    headers: Row,
+    columns: HashMap<String, usize>,
+    rows: Vec<Row>,
+}
+
+impl Table {
+    fn push(&mut self, record: Json) -> Result<(), String> {
+        let Json::Object(object) = record else {
+            return Err("expected an object".to_string());
+        };
+        let mut row = Row::new();
+        self.flatten(&mut row, "", object);
+        self.rows.push(row);
+        Ok(())
+    }
+
+    /// Adds the cells of `object` to `row`. Nested objects become columns
+    /// named with dotted paths, such as `address.city`.
+    fn flatten(&mut self, row: &mut Row, prefix: &str, object: Map<String, Json>) {
+        for (key, value) in object {
+            let name = if prefix.is_empty() {
+                key
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            let cell = match value {
+                Json::Object(object) => {
+                    self.flatten(row, &name, object);
+                    continue;
+                }
+                Json::Null => String::new(),
+                Json::String(text) => text,
+                // Numbers, booleans and arrays are written as JSON.
+                value => value.to_string(),
+            };
+            let col = *self.columns.entry(name).or_insert_with_key(|name| {
+                self.headers.push(name.clone());
+                self.headers.len() - 1
+            });
+            if row.len() <= col {
+                row.resize(col + 1, String::new());
+            }
+            row[col] = cell;
+        }
+    }
+
+    fn finish(mut self) -> (Row, Vec<Row>) {
+        let width = self.headers.len();
+        for row in &mut self.rows {
+            row.resize(width, String::new());
+        }
+        (self.headers, self.rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{contents, parser, row, rows};
+    use crate::{CSVParser, CsvError};
+
+    const PEOPLE: &str = "Name,Age,Member,Joined\n\
+                          Ann,31,true,2020-03-01\n\
+                          Bob,,no,soon\n";
+
+    #[test]
+    fn writes_every_cell_as_a_string_until_types_are_known() {
+        assert_eq!(
+            parser(PEOPLE).to_json(),
+            "[\n  \
+             {\"Name\":\"Ann\",\"Age\":\"31\",\"Member\":\"true\",\"Joined\":\"2020-03-01\"},\n  \
+             {\"Name\":\"Bob\",\"Age\":\"\",\"Member\":\"no\",\"Joined\":\"soon\"}\n\
+             ]\n"
+        );
+    }
+
+    #[test]
+    fn writes_cells_as_their_column_type() {
+        let mut parser = parser(PEOPLE);
+        parser.infer_types();
+        assert_eq!(
+            parser.to_jsonl(),
+            "{\"Name\":\"Ann\",\"Age\":31,\"Member\":true,\"Joined\":\"2020-03-01\"}\n\
+             {\"Name\":\"Bob\",\"Age\":null,\"Member\":false,\"Joined\":\"soon\"}\n"
+        );
+    }
+
+    #[test]
+    fn keys_by_index_without_headers_and_nulls_missing_cells() {
+        let mut parser = CSVParser::new();
+        parser.parse_from("a,b\nc\n".as_bytes()).unwrap();
+        assert_eq!(
+            parser.to_jsonl(),
+            "{\"0\":\"a\",\"1\":\"b\"}\n{\"0\":\"c\",\"1\":null}\n"
+        );
+        assert_eq!(CSVParser::new().to_json(), "[]\n");
+    }
+
+    #[test]
+    fn makes_repeated_header_names_unique() {
+        let parser = parser("id,name,name,name_2,id\n1,a,b,c,2\n");
+        assert_eq!(
+            parser.to_jsonl(),
+            "{\"id\":\"1\",\"name\":\"a\",\"name_3\":\"b\",\"name_2\":\"c\",\"id_2\":\"2\"}\n"
+        );
+    }
+
+    #[test]
+    fn reads_the_union_of_keys_in_the_order_first_seen() {
+        let parser = CSVParser::from_json(
+            r#"[{"name": "Ann", "age": 31}, {"city": "Rome", "name": "Bob", "tags": [1, 2]}]"#
+                .as_bytes(),
+        )
+        .unwrap();
+        let (headers, data) = contents(&parser);
+        assert_eq!(headers.unwrap(), row(&["name", "age", "city", "tags"]));
+        assert_eq!(
+            data,
+            rows(&[&["Ann", "31", "", ""], &["Bob", "", "Rome", "[1,2]"]])
+        );
+    }
+
+    #[test]
+    fn flattens_nested_objects_into_dotted_columns() {
+        let parser = CSVParser::from_jsonl(
+            "{\"name\": \"Ann\", \"address\": {\"city\": \"Rome\", \"geo\": {\"lat\": 41.9}}}\n\
+             \n\
+             {\"name\": null, \"active\": true}\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let (headers, data) = contents(&parser);
+        assert_eq!(
+            headers.unwrap(),
+            row(&["name", "address.city", "address.geo.lat", "active"])
+        );
+        assert_eq!(
+            data,
+            rows(&[&["Ann", "Rome", "41.9", ""], &["", "", "", "true"]])
+        );
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let written = parser(PEOPLE).to_json();
+        let read = CSVParser::from_json(written.as_bytes()).unwrap();
+        assert_eq!(contents(&read), contents(&parser(PEOPLE)));
+    }
+
+    #[test]
+    fn says_which_record_or_line_is_invalid() {
+        let err = CSVParser::from_json(r#"[{"a": 1}, 2]"#.as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid JSON: record 1: expected an object"
+        );
+
+        let err = CSVParser::from_jsonl("{\"a\": 1}\n\n[1]\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "invalid JSON: line 3: expected an object");
+
+        let err = CSVParser::from_jsonl("{\"a\": 1}\n{\"a\":\n".as_bytes()).unwrap_err();
+        let CsvError::InvalidJson { message } = err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert!(message.starts_with("line 2: "), "{}", message);
+    }
+}
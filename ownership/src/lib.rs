@@ -0,0 +1,56 @@
+//! Reading, editing and writing CSV files.
+//!
+//! [`CSVParser`] holds a whole file in memory for random access and edits,
+//! while [`CsvReader`] and [`CsvWriter`] stream records one at a time. All of
+//! them are configured with a [`Dialect`], which [`sniff`] can infer from a
+//! sample of unknown input. Every fallible operation returns a [`CsvError`].
+
+#![warn(missing_docs)]
+
+mod date;
+mod de;
+mod dialect;
+mod error;
+mod filter;
+mod group;
+mod history;
+mod join;
+mod json;
+mod markup;
+mod parser;
+mod processor;
+mod reader;
+mod schema;
+mod selection;
+mod ser;
+mod sniff;
+mod sort;
+mod table;
+#[cfg(test)]
+mod testing;
+mod transaction;
+mod value;
+mod writer;
+
+pub use date::Date;
+pub use dialect::{Dialect, Escape, LineTerminator};
+pub use error::{CsvError, Position};
+pub use filter::Filter;
+pub use group::{count, max, mean, min, sum, Aggregate, GroupBy};
+pub use history::Edit;
+pub use join::{hash_join, JoinKind, JoinOptions};
+pub use markup::{render_html, render_latex, render_markdown};
+pub use parser::{resolve_column, CSVParser};
+pub use processor::CSVProcessor;
+pub use reader::{CsvReader, Quarantined, RaggedRows};
+pub use schema::{ColumnRule, Problem, Schema, ValidationReport, Violation};
+pub use selection::Selection;
+pub use sniff::{sniff, sniff_path, Sniffed, DEFAULT_SAMPLE_SIZE};
+pub use sort::{Collation, Order, SortKey};
+pub use table::{display_cell, render_table, TableOptions};
+pub use transaction::Transaction;
+pub use value::{ColumnInference, ColumnType, Value};
+pub use writer::{CsvWriter, QuoteStyle};
+
+/// A single record: one string per field.
+pub type Row = Vec<String>;
@@ -1,107 +1,674 @@
-use std::error::Error;
+use std::collections::VecDeque;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut parser = CSVParser::new();
-    let mut processor = CSVProcessor::new(&mut parser);
-    processor.process_csv()?;
+use clap::{Parser, Subcommand, ValueEnum};
 
-    Ok(())
+mod view;
+
+use ownership::{
+    resolve_column, Aggregate, CSVParser, CSVProcessor, Collation, CsvError, CsvReader, CsvWriter,
+    Dialect, Filter, JoinKind, JoinOptions, Order, Quarantined, RaggedRows, Row, Schema, SortKey,
+    TableOptions,
+};
+
+// Exit codes, following sysexits.h where one fits. Usage errors share 2 with
+// the ones clap reports.
+const EXIT_NOT_FOUND: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_DATA_ERROR: u8 = 65;
+const EXIT_NO_INPUT: u8 = 66;
+const EXIT_IO_ERROR: u8 = 74;
+const EXIT_CONFIG: u8 = 78;
+
+/// Read, inspect and edit CSV files.
+///
+/// FILE may be `-` to read from stdin, and output goes to stdout unless
+/// stated otherwise.
+#[derive(Parser)]
+#[command(name = "csv", version)]
+struct Cli {
+    /// Field delimiter; `\t` or `tab` for tabs.
+    #[arg(short, long, global = true, default_value = ",", value_parser = parse_delimiter)]
+    delimiter: char,
+
+    /// Read the first record as data rather than as a header row. Rows are
+    /// then counted from the first record and columns can only be given by
+    /// index.
+    #[arg(long, global = true)]
+    no_headers: bool,
+
+    /// What to do with rows whose field count differs from the header's,
+    /// or the first row's: keep them, fail, pad short rows, truncate long
+    /// ones, do both, or leave them out and list them on stderr.
+    #[arg(long, global = true, value_enum, default_value_t = Ragged::Allow)]
+    ragged: Ragged,
+
+    #[command(subcommand)]
+    command: Command,
 }
 
-struct CSVProcessor<'a> {
-    parser: &'a mut CSVParser,
+#[derive(Subcommand)]
+enum Command {
+    /// Print one cell.
+    Get {
+        file: String,
+        row: usize,
+        /// Header name, or column index.
+        col: String,
+    },
+    /// Change one cell and write out the whole file.
+    Set {
+        file: String,
+        row: usize,
+        /// Header name, or column index.
+        col: String,
+        value: String,
+        /// Write here instead of stdout. May be the input file.
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Concatenate files, keeping only the first file's header row.
+    Cat {
+        #[arg(default_value = "-")]
+        files: Vec<String>,
+    },
+    /// Print the first rows.
+    Head {
+        #[arg(short = 'n', long, default_value_t = 10)]
+        rows: usize,
+        #[arg(default_value = "-")]
+        file: String,
+    },
+    /// Print the last rows.
+    Tail {
+        #[arg(short = 'n', long, default_value_t = 10)]
+        rows: usize,
+        #[arg(default_value = "-")]
+        file: String,
+    },
+    /// Print the rows matching an expression such as
+    /// `Age > 30 && City != "Paris"`.
+    ///
+    /// Columns are header names, `#0`-style indices or
+    /// `backquoted names`; strings are quoted. Comparisons are numeric when
+    /// both sides are numbers. Combine them with &&, || and !.
+    Filter { file: String, expression: String },
+    /// Sort rows by one or more columns, keeping the header first.
+    Sort {
+        #[arg(default_value = "-")]
+        file: String,
+        /// COLUMN[:asc|desc][:numeric|lexical|natural|date], a header or
+        /// index. Repeat to break ties; cells are compared to suit the
+        /// column unless told how.
+        #[arg(short, long = "key", required = true, value_parser = parse_sort_key)]
+        keys: Vec<SortKey>,
+    },
+    /// Summarize rows grouped by the values of one or more columns.
+    Group {
+        #[arg(default_value = "-")]
+        file: String,
+        /// Column to group by, a header or index. Repeat for several.
+        #[arg(short, long = "by", required = true)]
+        by: Vec<String>,
+        /// count, or sum, mean, min or max and a column, as in `mean:Age`.
+        /// Repeat for several.
+        #[arg(short, long = "agg", required = true, value_parser = parse_aggregate)]
+        aggs: Vec<Aggregate>,
+    },
+    /// Infer each column's type: integer, float, boolean, date, datetime or
+    /// text. Prints one row per column with the best type other than text,
+    /// how many cells are null and how many do not fit that best type.
+    Types {
+        #[arg(default_value = "-")]
+        file: String,
+    },
+    /// Check a file against a schema of column rules, printing one line per
+    /// violation and failing if there are any.
+    Validate {
+        #[arg(default_value = "-")]
+        file: String,
+        /// A TOML file, or a JSON file if its name ends in `.json`.
+        #[arg(short, long)]
+        schema: String,
+    },
+    /// Convert rows to a JSON array of objects keyed by header, or by column
+    /// index with --no-headers.
+    Json {
+        #[arg(default_value = "-")]
+        file: String,
+        /// Write JSON Lines, one object per line, instead of an array.
+        #[arg(short, long)]
+        lines: bool,
+        /// Write numbers, booleans and nulls for columns whose type can be
+        /// inferred, instead of only strings.
+        #[arg(short, long)]
+        typed: bool,
+    },
+    /// Convert a JSON array of objects to CSV, with every key found as a
+    /// header. Nested objects become `parent.child` columns.
+    FromJson {
+        #[arg(default_value = "-")]
+        file: String,
+        /// Read JSON Lines, one object per line, instead of an array.
+        #[arg(short, long)]
+        lines: bool,
+    },
+    /// Join two files on key columns.
+    ///
+    /// RIGHT is held in memory while LEFT is streamed, so make RIGHT the
+    /// smaller file.
+    Join {
+        left: String,
+        right: String,
+        /// Key column, a header or index. Repeat for several.
+        #[arg(long = "on", required = true)]
+        on: Vec<String>,
+        /// RIGHT's key columns, where they differ from LEFT's.
+        #[arg(long = "right-on")]
+        right_on: Vec<String>,
+        /// Which rows to keep besides matches.
+        #[arg(short, long, value_enum, default_value_t = Kind::Inner)]
+        kind: Kind,
+        /// Appended to headers found on both sides.
+        #[arg(long, num_args = 2, value_names = ["LEFT", "RIGHT"], default_values = ["_left", "_right"])]
+        suffixes: Vec<String>,
+    },
+    /// Print rows as an aligned table.
+    Table {
+        #[arg(default_value = "-")]
+        file: String,
+        /// Rows per page; all rows when omitted.
+        #[arg(short = 'n', long)]
+        rows: Option<usize>,
+        /// Which page of --rows rows to show, counted from 1.
+        #[arg(short, long, default_value_t = 1, requires = "rows")]
+        page: usize,
+        /// Cut cells wider than this; 0 never cuts.
+        #[arg(short, long, default_value_t = 40)]
+        width: usize,
+        /// Separate columns with spaces instead of drawing borders.
+        #[arg(long)]
+        plain: bool,
+    },
+    /// Print rows as a Markdown, HTML or LaTeX table.
+    Export {
+        #[arg(default_value = "-")]
+        file: String,
+        #[arg(short, long, value_enum)]
+        format: Format,
+    },
+    /// Browse and edit a file full screen.
+    View {
+        file: String,
+        /// Save here instead of over FILE.
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Count the rows.
+    Count {
+        #[arg(default_value = "-")]
+        file: String,
+    },
+    /// Run the ownership walkthrough from the README.
+    Demo {
+        #[arg(default_value = "ownership/data.csv")]
+        input: String,
+        #[arg(default_value = "ownership/updated_data.csv")]
+        output: String,
+    },
 }
 
-impl<'a> CSVProcessor<'a> {
-    fn new(parser: &'a mut CSVParser) -> Self {
-        CSVProcessor { parser }
-    }
+#[derive(Clone, Copy, ValueEnum)]
+enum Kind {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
 
-    fn process_csv(&mut self) -> Result<(), Box<dyn Error>> {
-        self.parser.parse_csv("ownership/data.csv")?;
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Markdown,
+    Html,
+    Latex,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Ragged {
+    Allow,
+    Error,
+    Pad,
+    Truncate,
+    Fit,
+    Quarantine,
+}
 
-        if let Some(row) = self.parser.get_row(1) {
-            println!("Row 1: {:?}", row);
+impl From<Ragged> for RaggedRows {
+    fn from(ragged: Ragged) -> Self {
+        match ragged {
+            Ragged::Allow => RaggedRows::Allow,
+            Ragged::Error => RaggedRows::Error,
+            Ragged::Pad => RaggedRows::Pad,
+            Ragged::Truncate => RaggedRows::Truncate,
+            Ragged::Fit => RaggedRows::Fit,
+            Ragged::Quarantine => RaggedRows::Quarantine,
         }
-        if let Some(cell) = self.parser.get_cell(2, 3) {
-            println!("Cell (2, 3): {}", cell);
+    }
+}
+
+impl From<Kind> for JoinKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Inner => JoinKind::Inner,
+            Kind::Left => JoinKind::Left,
+            Kind::Right => JoinKind::Right,
+            Kind::Full => JoinKind::Full,
         }
+    }
+}
 
-        self.parser.update_cell(2, 3, "Updated Value")?;
-        self.parser.write_csv("ownership/updated_data.csv")?;
-        self.parser.display_csv()?;
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        // The reader went away, as with `csv cat big.csv | head`.
+        Err(CsvError::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("csv: {}", err);
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
 
-        Ok(())
+fn exit_code(err: &CsvError) -> u8 {
+    match err {
+        CsvError::Io(err) if err.kind() == io::ErrorKind::NotFound => EXIT_NO_INPUT,
+        CsvError::Io(_) => EXIT_IO_ERROR,
+        CsvError::RowOutOfBounds { .. }
+        | CsvError::ColumnOutOfBounds { .. }
+        | CsvError::UnknownColumn(_) => EXIT_NOT_FOUND,
+        CsvError::InvalidExpression { .. } => EXIT_USAGE,
+        CsvError::InvalidSchema { .. } => EXIT_CONFIG,
+        _ => EXIT_DATA_ERROR,
     }
 }
 
-type Row = Vec<String>;
+fn run(cli: Cli) -> Result<(), CsvError> {
+    let dialect = Dialect::new().delimiter(cli.delimiter);
+    let headers = !cli.no_headers;
+    let ragged = RaggedRows::from(cli.ragged);
 
-struct CSVParser {
-    data: Vec<Row>,
+    match cli.command {
+        Command::Get { file, row, col } => {
+            let mut reader = open_reader(&file, dialect, headers, ragged)?;
+            // Every record up to `row` is read, so a malformed one is
+            // reported rather than skipped.
+            let mut record = Row::new();
+            for rows in 0..=row {
+                if !reader.read_record(&mut record)? {
+                    return Err(CsvError::RowOutOfBounds { row, rows });
+                }
+            }
+            report_quarantined(&file, &reader.take_quarantined(), dialect)?;
+            let col_index = resolve_column(reader.headers()?, &col, record.len())?;
+            let cell = record.get(col_index).ok_or(CsvError::ColumnOutOfBounds {
+                col: col_index,
+                columns: record.len(),
+            })?;
+            println!("{}", cell);
+        }
+        Command::Set {
+            file,
+            row,
+            col,
+            value,
+            output,
+        } => {
+            let mut parser = load(&file, dialect, headers, ragged)?;
+            let col_index = resolve_column(parser.headers(), &col, parser.column_count())?;
+            parser.update_cell(row, col_index, &value)?;
+            match output {
+                Some(path) => parser.write_csv(&path)?,
+                None => parser.write_to(io::stdout().lock())?,
+            }
+        }
+        Command::Cat { files } => {
+            let mut writer = stdout_writer(dialect);
+            for (i, file) in files.iter().enumerate() {
+                let mut reader = open_reader(file, dialect, headers, ragged)?;
+                if let (0, Some(header)) = (i, reader.headers()?) {
+                    writer.write_record(header)?;
+                }
+                for row in reader.by_ref() {
+                    writer.write_record(&row?)?;
+                }
+                report_quarantined(file, &reader.take_quarantined(), dialect)?;
+            }
+            writer.flush()?;
+        }
+        Command::Head { rows, file } => {
+            let mut reader = open_reader(&file, dialect, headers, ragged)?;
+            let mut writer = stdout_writer(dialect);
+            if let Some(header) = reader.headers()? {
+                writer.write_record(header)?;
+            }
+            for row in reader.by_ref().take(rows) {
+                writer.write_record(&row?)?;
+            }
+            writer.flush()?;
+            report_quarantined(&file, &reader.take_quarantined(), dialect)?;
+        }
+        Command::Tail { rows, file } => {
+            let mut reader = open_reader(&file, dialect, headers, ragged)?;
+            let mut writer = stdout_writer(dialect);
+            if let Some(header) = reader.headers()? {
+                writer.write_record(header)?;
+            }
+            // Only the last `rows` records are ever held in memory.
+            let mut last: VecDeque<Row> = VecDeque::with_capacity(rows);
+            for row in reader.by_ref() {
+                let row = row?;
+                if rows == 0 {
+                    continue;
+                }
+                if last.len() == rows {
+                    last.pop_front();
+                }
+                last.push_back(row);
+            }
+            for row in &last {
+                writer.write_record(row)?;
+            }
+            writer.flush()?;
+            report_quarantined(&file, &reader.take_quarantined(), dialect)?;
+        }
+        Command::Filter { file, expression } => {
+            let mut reader = open_reader(&file, dialect, headers, ragged)?;
+            let mut writer = stdout_writer(dialect);
+            let header = reader.headers()?.cloned();
+            let filter = Filter::new(&expression, header.as_deref())?;
+            if let Some(header) = &header {
+                writer.write_record(header)?;
+            }
+            for row in reader.by_ref() {
+                let row = row?;
+                if filter.matches(&row) {
+                    writer.write_record(&row)?;
+                }
+            }
+            writer.flush()?;
+            report_quarantined(&file, &reader.take_quarantined(), dialect)?;
+        }
+        Command::Sort { file, keys } => {
+            let mut parser = load(&file, dialect, headers, ragged)?;
+            parser.sort_by_keys(&keys)?;
+            parser.write_to(io::stdout().lock())?;
+        }
+        Command::Group { file, by, aggs } => {
+            let parser = load(&file, dialect, headers, ragged)?;
+            let by: Vec<&str> = by.iter().map(String::as_str).collect();
+            let groups = parser.group_by_columns(&by).agg(aggs)?;
+            groups.write_to(io::stdout().lock())?;
+        }
+        Command::Types { file } => {
+            let mut parser = load(&file, dialect, headers, ragged)?;
+            let mut writer = stdout_writer(dialect);
+            let header = ["column", "type", "best_fit", "nulls", "failures"].map(String::from);
+            writer.write_record(&header)?;
+            for inference in parser.infer_types() {
+                writer.write_record(&[
+                    inference.name,
+                    inference.column_type.to_string(),
+                    inference
+                        .best_fit
+                        .map_or_else(String::new, |column_type| column_type.to_string()),
+                    inference.nulls.to_string(),
+                    inference.failures.to_string(),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        Command::Validate { file, schema } => {
+            let schema = Schema::from_path(&schema)?;
+            let parser = load(&file, dialect, headers, ragged)?;
+            let report = parser.validate(&schema)?;
+            write!(io::stdout().lock(), "{}", report)?;
+            if !report.is_valid() {
+                return Err(CsvError::SchemaViolated {
+                    violations: report.violations.len(),
+                });
+            }
+        }
+        Command::Json { file, lines, typed } => {
+            let mut parser = load(&file, dialect, headers, ragged)?;
+            if typed {
+                parser.infer_types();
+            }
+            let json = if lines {
+                parser.to_jsonl()
+            } else {
+                parser.to_json()
+            };
+            io::stdout().lock().write_all(json.as_bytes())?;
+        }
+        Command::FromJson { file, lines } => {
+            let parser = if lines {
+                CSVParser::from_jsonl(open(&file)?)?
+            } else {
+                CSVParser::from_json(open(&file)?)?
+            };
+            let parser = parser.with_dialect(dialect);
+            parser.write_to(io::stdout().lock())?;
+        }
+        Command::Join {
+            left,
+            right,
+            on,
+            right_on,
+            kind,
+            suffixes,
+        } => {
+            let on: Vec<&str> = on.iter().map(String::as_str).collect();
+            let mut options = JoinOptions::new(&on).kind(kind.into());
+            if !right_on.is_empty() {
+                let right_on: Vec<&str> = right_on.iter().map(String::as_str).collect();
+                options = options.right_on(&right_on);
+            }
+            options = options.suffixes(&suffixes[0], &suffixes[1]);
+            let right_parser = load(&right, dialect, headers, ragged)?;
+            let mut reader = open_reader(&left, dialect, headers, ragged)?;
+            ownership::hash_join(
+                &mut reader,
+                &right_parser,
+                &options,
+                &mut stdout_writer(dialect),
+            )?;
+            report_quarantined(&left, &reader.take_quarantined(), dialect)?;
+        }
+        Command::Table {
+            file,
+            rows,
+            page,
+            width,
+            plain,
+        } => {
+            let parser = load(&file, dialect, headers, ragged)?;
+            let mut options = TableOptions::new()
+                .max_cell_width(Some(width).filter(|&width| width > 0))
+                .borders(!plain);
+            if let Some(rows) = rows {
+                options = options.page(page, rows);
+            }
+            print!("{}", parser.to_table(&options));
+        }
+        Command::Export { file, format } => {
+            let parser = load(&file, dialect, headers, ragged)?;
+            let table = match format {
+                Format::Markdown => parser.to_markdown(),
+                Format::Html => parser.to_html(),
+                Format::Latex => parser.to_latex(),
+            };
+            io::stdout().lock().write_all(table.as_bytes())?;
+        }
+        Command::View { file, output } => {
+            let mut parser = load(&file, dialect, headers, ragged)?;
+            view::run(&mut parser, output.as_deref().unwrap_or(&file))?;
+        }
+        Command::Count { file } => {
+            let mut reader = open_reader(&file, dialect, headers, ragged)?;
+            let mut count = 0u64;
+            for row in reader.by_ref() {
+                row?;
+                count += 1;
+            }
+            report_quarantined(&file, &reader.take_quarantined(), dialect)?;
+            println!("{}", count);
+        }
+        Command::Demo { input, output } => {
+            let mut parser = CSVParser::new();
+            let mut processor = CSVProcessor::new(&mut parser);
+            processor.process_csv(&input, &output)?;
+        }
+    }
+    Ok(())
 }
 
-impl CSVParser {
-    fn new() -> Self {
-        CSVParser { data: Vec::new() }
+fn open(path: &str) -> Result<Box<dyn Read>, CsvError> {
+    if path == "-" {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(File::open(path)?))
     }
+}
 
-    fn parse_csv(&mut self, file_path: &str) -> Result<(), Box<dyn Error>> {
-        let file = File::open(file_path)?;
-        let reader = BufReader::new(file);
-        for line in reader.lines() {
-            let line = line?;
-            let values: Row = line.split(",").map(|s| s.to_string()).collect();
-            self.data.push(values);
-        }
-        Ok(())
+fn open_reader(
+    path: &str,
+    dialect: Dialect,
+    headers: bool,
+    ragged: RaggedRows,
+) -> Result<CsvReader<Box<dyn Read>>, CsvError> {
+    Ok(CsvReader::new(open(path)?)
+        .with_dialect(dialect)
+        .has_headers(headers)
+        .ragged_rows(ragged))
+}
+
+/// Reads a whole file into a parser, reporting any rows set aside by
+/// `--ragged quarantine` on stderr.
+fn load(
+    path: &str,
+    dialect: Dialect,
+    headers: bool,
+    ragged: RaggedRows,
+) -> Result<CSVParser, CsvError> {
+    let mut parser = CSVParser::new()
+        .with_dialect(dialect)
+        .has_headers(headers)
+        .ragged_rows(ragged);
+    parser.parse_from(open(path)?)?;
+    report_quarantined(path, parser.quarantined(), dialect)?;
+    Ok(parser)
+}
+
+/// Prints each set-aside row on stderr, after the file and where it starts.
+fn report_quarantined(
+    path: &str,
+    quarantined: &[Quarantined],
+    dialect: Dialect,
+) -> Result<(), CsvError> {
+    let mut writer = CsvWriter::new(io::stderr().lock()).with_dialect(dialect);
+    for record in quarantined {
+        writer.flush()?;
+        eprint!("csv: {}: {}: quarantined: ", path, record.position);
+        writer.write_record(&record.row)?;
     }
+    writer.flush()
+}
+
+fn stdout_writer(dialect: Dialect) -> CsvWriter<io::StdoutLock<'static>> {
+    CsvWriter::new(io::stdout().lock()).with_dialect(dialect)
+}
 
-    fn get_row(&self, row_index: usize) -> Option<&Row> {
-        self.data.get(row_index)
+/// Reads `COLUMN[:ORDER][:COLLATION]`. Only known suffixes are split off,
+/// so column names may contain colons.
+fn parse_sort_key(value: &str) -> Result<SortKey, String> {
+    let mut column = value;
+    let mut key = SortKey::new("");
+    while let Some((rest, suffix)) = column.rsplit_once(':') {
+        key = match suffix.to_ascii_lowercase().as_str() {
+            "asc" => key.order(Order::Asc),
+            "desc" => key.order(Order::Desc),
+            "auto" => key.collation(Collation::Auto),
+            "numeric" => key.collation(Collation::Numeric),
+            "lexical" => key.collation(Collation::Lexical),
+            "natural" => key.collation(Collation::Natural),
+            "date" => key.collation(Collation::Date),
+            _ => break,
+        };
+        column = rest;
     }
+    if column.is_empty() {
+        return Err(format!("no column in {:?}", value));
+    }
+    key.column = column.to_string();
+    Ok(key)
+}
 
-    fn get_cell(&self, row_index: usize, col_index: usize) -> Option<&str> {
-        self.data
-            .get(row_index)
-            .and_then(|row| row.get(col_index))
-            .map(|cell| cell.as_str())
+fn parse_aggregate(value: &str) -> Result<Aggregate, String> {
+    match value.split_once(':') {
+        None if value == "count" => Ok(ownership::count()),
+        Some(("sum", column)) => Ok(ownership::sum(column)),
+        Some(("mean", column)) => Ok(ownership::mean(column)),
+        Some(("min", column)) => Ok(ownership::min(column)),
+        Some(("max", column)) => Ok(ownership::max(column)),
+        _ => Err(format!(
+            "expected count, sum:COLUMN, mean:COLUMN, min:COLUMN or max:COLUMN, got {:?}",
+            value
+        )),
     }
+}
 
-    fn update_cell(
-        &mut self,
-        row_index: usize,
-        col_index: usize,
-        value: &str,
-    ) -> Result<(), String> {
-        match self.data.get_mut(row_index) {
-            Some(row) => match row.get_mut(col_index) {
-                Some(cell) => {
-                    *cell = value.to_string();
-                    Ok(())
-                }
-                None => Err(format!("Invalid column index: {}", col_index)),
-            },
-            None => Err(format!("Invalid row index: {}", row_index)),
-        }
+fn parse_delimiter(value: &str) -> Result<char, String> {
+    if value == "\\t" || value == "tab" {
+        return Ok('\t');
+    }
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!("expected a single character, got {:?}", value)),
     }
+}
 
-    fn write_csv(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
-        let mut file = File::create(file_path)?;
-        for row in &self.data {
-            let line = row.join(",");
-            writeln!(file, "{}", line)?;
-        }
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_sort_key_with_its_order_and_collation() {
+        let key = parse_sort_key("Age:desc:numeric").unwrap();
+        assert_eq!(
+            key,
+            SortKey::new("Age")
+                .order(Order::Desc)
+                .collation(Collation::Numeric)
+        );
+        let key = parse_sort_key("Name:NATURAL").unwrap();
+        assert_eq!(key, SortKey::new("Name").collation(Collation::Natural));
+        assert_eq!(parse_sort_key("3").unwrap(), SortKey::new("3"));
     }
 
-    fn display_csv(&self) -> Result<(), Box<dyn Error>> {
-        for row in &self.data {
-            let line = row.join(",");
-            println!("{}", line);
-        }
-        Ok(())
+    #[test]
+    fn keeps_colons_that_are_part_of_the_column() {
+        let key = parse_sort_key("Start: time:desc").unwrap();
+        assert_eq!(key, SortKey::new("Start: time").order(Order::Desc));
+        assert_eq!(parse_sort_key("a:b").unwrap().column, "a:b");
+    }
+
+    #[test]
+    fn needs_a_column_in_a_sort_key() {
+        assert!(parse_sort_key(":desc").is_err());
+        assert!(parse_sort_key("").is_err());
     }
 }
@@ -0,0 +1,253 @@
+use crate::Row;
+
+/// Renders `rows` under `headers` as a GitHub-flavored Markdown table.
+///
+/// Markdown tables need a header row, so without `headers` it is left
+/// blank. Columns whose cells are all numbers are right-aligned. Pipes are
+/// escaped, as are `&`, `<` and `>` so cells are not read as HTML, and line
+/// breaks become `<br>`.
+pub fn render_markdown(headers: Option<&[String]>, rows: &[Row]) -> String {
+    let columns = column_count(headers, rows);
+    let line = |cells: &[String]| {
+        let mut line = String::from("|");
+        for col in 0..columns {
+            let cell = cells.get(col).map_or("", String::as_str);
+            line.push(' ');
+            line.push_str(&markdown_escape(cell));
+            line.push_str(" |");
+        }
+        line.push('\n');
+        line
+    };
+
+    let mut out = line(headers.unwrap_or_default());
+    out.push('|');
+    for numeric in numeric_columns(rows, columns) {
+        out.push_str(if numeric { " ---: |" } else { " --- |" });
+    }
+    out.push('\n');
+    for row in rows {
+        out.push_str(&line(row));
+    }
+    out
+}
+
+/// Renders `rows` under `headers` as an HTML `<table>`, with the headers, if
+/// any, in a `<thead>`.
+///
+/// `&`, `<`, `>` and quotes are escaped and line breaks become `<br>`, so
+/// any cell is safe to embed in a page.
+pub fn render_html(headers: Option<&[String]>, rows: &[Row]) -> String {
+    let columns = column_count(headers, rows);
+    let line = |cells: &[String], tag: &str| {
+        let mut line = String::from("    <tr>");
+        for col in 0..columns {
+            let cell = cells.get(col).map_or("", String::as_str);
+            line.push_str(&format!("<{}>{}</{}>", tag, html_escape(cell), tag));
+        }
+        line.push_str("</tr>\n");
+        line
+    };
+
+    let mut out = String::from("<table>\n");
+    if let Some(headers) = headers {
+        out.push_str("  <thead>\n");
+        out.push_str(&line(headers, "th"));
+        out.push_str("  </thead>\n");
+    }
+    out.push_str("  <tbody>\n");
+    for row in rows {
+        out.push_str(&line(row, "td"));
+    }
+    out.push_str("  </tbody>\n</table>\n");
+    out
+}
+
+/// Renders `rows` under `headers` as a LaTeX `tabular` ruled with `\hline`.
+///
+/// Columns whose cells are all numbers are right-aligned. Characters LaTeX
+/// treats specially are escaped and line breaks become spaces.
+pub fn render_latex(headers: Option<&[String]>, rows: &[Row]) -> String {
+    let columns = column_count(headers, rows);
+    let line = |cells: &[String]| {
+        let cells: Vec<String> = (0..columns)
+            .map(|col| latex_escape(cells.get(col).map_or("", String::as_str)))
+            .collect();
+        format!("{} \\\\\n", cells.join(" & "))
+    };
+
+    let alignment: String = numeric_columns(rows, columns)
+        .map(|numeric| if numeric { 'r' } else { 'l' })
+        .collect();
+    let mut out = format!("\\begin{{tabular}}{{{}}}\n\\hline\n", alignment);
+    if let Some(headers) = headers {
+        out.push_str(&line(headers));
+        out.push_str("\\hline\n");
+    }
+    for row in rows {
+        out.push_str(&line(row));
+    }
+    out.push_str("\\hline\n\\end{tabular}\n");
+    out
+}
+
+fn column_count(headers: Option<&[String]>, rows: &[Row]) -> usize {
+    rows.iter()
+        .map(|row| row.len())
+        .chain(headers.map(|headers| headers.len()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// For each column, whether it has a number and nothing else but empty cells.
+fn numeric_columns(rows: &[Row], columns: usize) -> impl Iterator<Item = bool> + '_ {
+    (0..columns).map(move |col| {
+        let mut cells = rows
+            .iter()
+            .filter_map(|row| row.get(col))
+            .map(|cell| cell.trim())
+            .filter(|cell| !cell.is_empty())
+            .peekable();
+        cells.peek().is_some() && cells.all(|cell| cell.parse::<f64>().is_ok())
+    })
+}
+
+fn markdown_escape(cell: &str) -> String {
+    let mut escaped = String::with_capacity(cell.len());
+    let mut chars = cell.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '|' => escaped.push_str("\\|"),
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => escaped.push_str("<br>"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn html_escape(cell: &str) -> String {
+    let mut escaped = String::with_capacity(cell.len());
+    let mut chars = cell.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => escaped.push_str("<br>"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn latex_escape(cell: &str) -> String {
+    let mut escaped = String::with_capacity(cell.len());
+    for c in cell.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '<' => escaped.push_str("\\textless{}"),
+            '>' => escaped.push_str("\\textgreater{}"),
+            '|' => escaped.push_str("\\textbar{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' | '\t' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{row, rows};
+
+    #[test]
+    fn renders_markdown_with_numbers_right_aligned() {
+        let headers = row(&["Name", "Age"]);
+        let data = rows(&[&["Ann", "31"], &["Bob", ""], &["Cid"]]);
+        assert_eq!(
+            render_markdown(Some(&headers), &data),
+            "| Name | Age |\n\
+             | --- | ---: |\n\
+             | Ann | 31 |\n\
+             | Bob |  |\n\
+             | Cid |  |\n"
+        );
+    }
+
+    #[test]
+    fn leaves_the_markdown_header_blank_without_headers() {
+        assert_eq!(
+            render_markdown(None, &rows(&[&["a", "b"]])),
+            "|  |  |\n| --- | --- |\n| a | b |\n"
+        );
+    }
+
+    #[test]
+    fn escapes_pipes_and_html_in_markdown() {
+        let data = rows(&[&["a|b", "<b>&</b>", "one\r\ntwo\nthree"]]);
+        assert_eq!(
+            render_markdown(None, &data).lines().nth(2).unwrap(),
+            "| a\\|b | &lt;b&gt;&amp;&lt;/b&gt; | one<br>two<br>three |"
+        );
+    }
+
+    #[test]
+    fn renders_html_with_headers_in_a_thead() {
+        let headers = row(&["Name"]);
+        assert_eq!(
+            render_html(Some(&headers), &rows(&[&["Ann"]])),
+            "<table>\n  \
+             <thead>\n    <tr><th>Name</th></tr>\n  </thead>\n  \
+             <tbody>\n    <tr><td>Ann</td></tr>\n  </tbody>\n\
+             </table>\n"
+        );
+        assert!(!render_html(None, &rows(&[&["Ann"]])).contains("<thead>"));
+    }
+
+    #[test]
+    fn escapes_entities_in_html() {
+        let data = rows(&[&["<script>alert(\"x & 'y'\")</script>", "a\r\nb"]]);
+        assert_eq!(
+            render_html(None, &data).lines().nth(2).unwrap(),
+            "    <tr><td>&lt;script&gt;alert(&quot;x &amp; &#39;y&#39;&quot;)&lt;/script&gt;</td>\
+             <td>a<br>b</td></tr>"
+        );
+    }
+
+    #[test]
+    fn renders_latex_ruled_with_hlines() {
+        let headers = row(&["Name", "Age"]);
+        assert_eq!(
+            render_latex(Some(&headers), &rows(&[&["Ann", "31"], &["Bob", "4.5"]])),
+            "\\begin{tabular}{lr}\n\\hline\n\
+             Name & Age \\\\\n\\hline\n\
+             Ann & 31 \\\\\n\
+             Bob & 4.5 \\\\\n\
+             \\hline\n\\end{tabular}\n"
+        );
+    }
+
+    #[test]
+    fn escapes_latex_special_characters() {
+        let data = rows(&[&["50% of $5 & #1_{x}", "a\\b ~^ <|>", "one\ntwo"]]);
+        assert_eq!(
+            render_latex(None, &data).lines().nth(2).unwrap(),
+            "50\\% of \\$5 \\& \\#1\\_\\{x\\} & \
+             a\\textbackslash{}b \\textasciitilde{}\\textasciicircum{} \
+             \\textless{}\\textbar{}\\textgreater{} & one two \\\\"
+        );
+    }
+}
@@ -0,0 +1,985 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::dialect::Dialect;
+use crate::error::CsvError;
+use crate::filter::Filter;
+use crate::group::GroupBy;
+use crate::history::Edit;
+use crate::join::{self, JoinOptions};
+use crate::json;
+use crate::markup;
+use crate::reader::{CsvReader, Quarantined, RaggedRows};
+use crate::schema::{self, Schema, ValidationReport};
+use crate::selection::Selection;
+use crate::sort::{self, Order, SortKey};
+use crate::table::{self, TableOptions};
+use crate::transaction::Transaction;
+use crate::value::{self, ColumnInference, ColumnType, Value};
+use crate::{de, ser, writer, Row};
+
+/// An in-memory table of CSV records.
+///
+/// Records are read with [`CSVParser::parse_csv`] or [`CSVParser::parse_from`],
+/// inspected and edited by index or by header name, and written back out with
+/// [`CSVParser::write_csv`] or [`CSVParser::write_to`]. Every edit is kept in
+/// a [`CSVParser::history`] that can be undone and redone.
+#[derive(Clone, Debug)]
+pub struct CSVParser {
+    data: Vec<Row>,
+    dialect: Dialect,
+    has_headers: bool,
+    headers: Option<Row>,
+    ragged: RaggedRows,
+    quarantined: Vec<Quarantined>,
+    column_types: Option<Vec<ColumnType>>,
+    history: Vec<Edit>,
+    undone: Vec<Edit>,
+}
+
+impl CSVParser {
+    /// Creates an empty parser for RFC 4180 CSV without headers.
+    pub fn new() -> Self {
+        CSVParser {
+            data: Vec::new(),
+            dialect: Dialect::default(),
+            has_headers: false,
+            headers: None,
+            ragged: RaggedRows::Allow,
+            quarantined: Vec::new(),
+            column_types: None,
+            history: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// When set, the first record is kept as the header row rather than as
+    /// data, so row 0 is the first data row.
+    pub fn has_headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// Sets the dialect used for both reading and writing.
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Sets what is done with rows that have a different number of fields
+    /// than the header row or, without headers, the first row. By default
+    /// they are kept as they are.
+    pub fn ragged_rows(mut self, ragged: RaggedRows) -> Self {
+        self.ragged = ragged;
+        self
+    }
+
+    /// A parser holding `headers` and `data` as if they had been read.
+    pub(crate) fn from_parts(headers: Option<Row>, data: Vec<Row>, dialect: Dialect) -> Self {
+        CSVParser {
+            data,
+            dialect,
+            has_headers: headers.is_some(),
+            headers,
+            ragged: RaggedRows::Allow,
+            quarantined: Vec::new(),
+            column_types: None,
+            history: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// Reads a JSON array of objects, one per row. The headers are every
+    /// key found, in the order first seen; nested objects are flattened into
+    /// columns with dotted names such as `address.city`.
+    ///
+    /// Strings are kept as they are, null becomes an empty cell, and numbers,
+    /// booleans and arrays are written as JSON. Cells for keys a record lacks
+    /// are empty.
+    pub fn from_json<R: Read>(source: R) -> Result<Self, CsvError> {
+        let (headers, data) = json::from_json(source)?;
+        Ok(CSVParser::from_parts(
+            Some(headers).filter(|headers| !headers.is_empty()),
+            data,
+            Dialect::default(),
+        ))
+    }
+
+    /// Reads JSON Lines, one object per line, as [`CSVParser::from_json`]
+    /// reads an array.
+    pub fn from_jsonl<R: Read>(source: R) -> Result<Self, CsvError> {
+        let (headers, data) = json::from_jsonl(source)?;
+        Ok(CSVParser::from_parts(
+            Some(headers).filter(|headers| !headers.is_empty()),
+            data,
+            Dialect::default(),
+        ))
+    }
+
+    /// The dialect used for both reading and writing.
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Appends every record from the file at `file_path`.
+    pub fn parse_csv(&mut self, file_path: &str) -> Result<(), CsvError> {
+        self.parse_from(File::open(file_path)?)
+    }
+
+    /// Appends every record from `source`, e.g. stdin, a socket or an
+    /// in-memory buffer. The edit history is cleared.
+    ///
+    /// Nothing changes unless the whole of `source` is read: on an error the
+    /// parser holds what it held before.
+    pub fn parse_from<R: Read>(&mut self, source: R) -> Result<(), CsvError> {
+        let mut reader = CsvReader::new(source)
+            .with_dialect(self.dialect)
+            .has_headers(self.has_headers)
+            .ragged_rows(self.ragged);
+        let headers = reader.headers()?.cloned();
+        let data = reader.by_ref().collect::<Result<Vec<Row>, _>>()?;
+
+        if headers.is_some() {
+            self.headers = headers;
+        }
+        self.data.extend(data);
+        self.quarantined.extend(reader.take_quarantined());
+        self.history.clear();
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// The rows set aside while parsing by [`RaggedRows::Quarantine`], with
+    /// where each starts in its input, in the order they were read.
+    pub fn quarantined(&self) -> &[Quarantined] {
+        &self.quarantined
+    }
+
+    /// The header row, if the parser was configured with headers and has
+    /// read one.
+    pub fn headers(&self) -> Option<&Row> {
+        self.headers.as_ref()
+    }
+
+    /// The index of the column whose header is `name`.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.headers
+            .as_ref()
+            .and_then(|headers| headers.iter().position(|header| header == name))
+    }
+
+    /// The number of data rows, not counting the header row.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Whether there are no data rows.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The number of columns: the length of the header row or of the
+    /// longest data row, whichever is greater.
+    pub fn column_count(&self) -> usize {
+        self.headers
+            .iter()
+            .chain(&self.data)
+            .map(|row| row.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Every data row, in order.
+    pub fn rows(&self) -> &[Row] {
+        &self.data
+    }
+
+    /// The data row at `row_index`.
+    pub fn get_row(&self, row_index: usize) -> Option<&Row> {
+        self.data.get(row_index)
+    }
+
+    /// The cell at `row_index`, `col_index`.
+    pub fn get_cell(&self, row_index: usize, col_index: usize) -> Option<&str> {
+        self.data
+            .get(row_index)
+            .and_then(|row| row.get(col_index))
+            .map(|cell| cell.as_str())
+    }
+
+    /// The cell at `row_index`, `col_index` read as its column's type, as
+    /// declared with [`CSVParser::set_column_types`] or found by
+    /// [`CSVParser::infer_types`]. A cell that does not fit is read as text,
+    /// and without known types each cell is read as the type it fits best.
+    pub fn get_value(&self, row_index: usize, col_index: usize) -> Option<Value> {
+        let cell = self.get_cell(row_index, col_index)?;
+        let column_type = self
+            .column_types
+            .as_ref()
+            .and_then(|types| types.get(col_index));
+        Some(match column_type {
+            Some(&column_type) => {
+                Value::parse_as(cell, column_type).unwrap_or_else(|| Value::Text(cell.to_string()))
+            }
+            None => Value::parse(cell),
+        })
+    }
+
+    /// Infers each column's type from its cells, keeps the types for
+    /// [`CSVParser::get_value`] and reports, column by column, how many
+    /// cells are null and how many do not fit the best type other than text,
+    /// even when too few fit it for the column to get that type.
+    ///
+    /// A column gets the most specific type that at least 90% of its
+    /// non-null cells fit; integers count as floats and dates as datetimes.
+    /// Columns with no such type, or no non-null cells, are text.
+    pub fn infer_types(&mut self) -> Vec<ColumnInference> {
+        let inferences: Vec<ColumnInference> = (0..self.column_count())
+            .map(|col| {
+                let name = match self.headers.as_ref().and_then(|headers| headers.get(col)) {
+                    Some(name) => name.clone(),
+                    None => col.to_string(),
+                };
+                value::infer_column(name, &self.data, col)
+            })
+            .collect();
+        self.column_types = Some(
+            inferences
+                .iter()
+                .map(|inference| inference.column_type)
+                .collect(),
+        );
+        inferences
+    }
+
+    /// The type of each column, if declared or inferred.
+    pub fn column_types(&self) -> Option<&[ColumnType]> {
+        self.column_types.as_deref()
+    }
+
+    /// Declares the type of each column, in order. Columns past the end of
+    /// `types` are read as if their type were unknown.
+    ///
+    /// Types follow their columns when columns are swapped, but are
+    /// forgotten when a column is inserted or removed, or such an edit is
+    /// undone or redone.
+    pub fn set_column_types(&mut self, types: Vec<ColumnType>) {
+        self.column_types = Some(types);
+    }
+
+    /// Checks every data row against `schema` and reports each violation,
+    /// or fails if one of the schema's patterns is not a valid regular
+    /// expression.
+    ///
+    /// A cell is checked for, in turn, nulls, its type, its pattern, its
+    /// range and its allowed values, and only the first problem found is
+    /// reported; then, in a unique column, for repeats.
+    pub fn validate(&self, schema: &Schema) -> Result<ValidationReport, CsvError> {
+        schema::validate(schema, self.headers.as_ref(), &self.data)
+    }
+
+    /// The data rows for which `predicate` holds, borrowed in order.
+    pub fn filter<F>(&self, mut predicate: F) -> Selection<'_>
+    where
+        F: FnMut(&Row) -> bool,
+    {
+        let rows = self
+            .data
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| predicate(row))
+            .collect();
+        Selection::new(self.headers.as_ref(), rows, self.dialect)
+    }
+
+    /// The data rows matching a [`Filter`] expression such as
+    /// `Age > 30 && City != "Paris"`, borrowed in order.
+    pub fn filter_expr(&self, expression: &str) -> Result<Selection<'_>, CsvError> {
+        let filter = Filter::new(expression, self.headers.as_deref())?;
+        Ok(self.filter(|row| filter.matches(row)))
+    }
+
+    /// Groups the data rows by the values in `column`, a header or an index,
+    /// ready to be summarized with [`GroupBy::agg`].
+    pub fn group_by(&self, column: &str) -> GroupBy<'_> {
+        self.group_by_columns(&[column])
+    }
+
+    /// Groups the data rows by their values in all of `columns`.
+    pub fn group_by_columns(&self, columns: &[&str]) -> GroupBy<'_> {
+        GroupBy::new(
+            self,
+            columns.iter().map(|column| column.to_string()).collect(),
+        )
+    }
+
+    /// Joins this parser's rows, on the left, to `right`'s into a new parser
+    /// in this parser's dialect. Rows come out in left order, followed by any
+    /// unmatched right rows in right order.
+    ///
+    /// Matching uses a hash index over `right`'s keys; to join a file too
+    /// large to hold in memory, stream it with [`hash_join`](crate::hash_join).
+    pub fn join(&self, right: &CSVParser, options: &JoinOptions) -> Result<CSVParser, CsvError> {
+        join::join(self, right, options)
+    }
+
+    /// Overwrites the cell at `row_index`, `col_index`.
+    pub fn update_cell(
+        &mut self,
+        row_index: usize,
+        col_index: usize,
+        value: &str,
+    ) -> Result<(), CsvError> {
+        let rows = self.data.len();
+        let row = self.data.get(row_index).ok_or(CsvError::RowOutOfBounds {
+            row: row_index,
+            rows,
+        })?;
+        let old = row.get(col_index).ok_or(CsvError::ColumnOutOfBounds {
+            col: col_index,
+            columns: row.len(),
+        })?;
+        self.commit(Edit::UpdateCell {
+            row: row_index,
+            col: col_index,
+            old: old.to_string(),
+            new: value.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Appends `row` after the last data row.
+    pub fn push_row(&mut self, row: Row) {
+        self.commit(Edit::InsertRow {
+            row: self.data.len(),
+            values: row,
+        });
+    }
+
+    /// Inserts `row` before the data row at `row_index`, shifting later rows
+    /// down. `row_index` may be the number of rows, to append.
+    pub fn insert_row(&mut self, row_index: usize, row: Row) -> Result<(), CsvError> {
+        self.check_row(row_index, self.data.len() + 1)?;
+        self.commit(Edit::InsertRow {
+            row: row_index,
+            values: row,
+        });
+        Ok(())
+    }
+
+    /// Removes and returns the data row at `row_index`, shifting later rows
+    /// up.
+    pub fn remove_row(&mut self, row_index: usize) -> Result<Row, CsvError> {
+        self.check_row(row_index, self.data.len())?;
+        let values = self.data[row_index].clone();
+        self.commit(Edit::RemoveRow {
+            row: row_index,
+            values: values.clone(),
+        });
+        Ok(values)
+    }
+
+    /// Inserts a column before `col_index`, shifting later columns right.
+    /// `col_index` may be [`CSVParser::column_count`], to append.
+    ///
+    /// Each row's new cell is computed by `value` from the row as it was
+    /// before the insert. With headers, the new column is headed `name`;
+    /// otherwise `name` is unused. Rows too short to reach `col_index` are
+    /// padded with empty cells first.
+    pub fn insert_column<F>(
+        &mut self,
+        col_index: usize,
+        name: &str,
+        value: F,
+    ) -> Result<(), CsvError>
+    where
+        F: FnMut(&Row) -> String,
+    {
+        self.check_column(col_index, self.column_count() + 1)?;
+        let values = self
+            .headers
+            .as_ref()
+            .map(|_| name.to_string())
+            .into_iter()
+            .chain(self.data.iter().map(value))
+            .collect();
+        self.commit(Edit::InsertColumn {
+            col: col_index,
+            values,
+            lengths: self.row_lengths(),
+        });
+        Ok(())
+    }
+
+    /// Removes the column at `col_index` from the headers and every row,
+    /// shifting later columns left. Rows too short to have the column are
+    /// left as they are.
+    pub fn remove_column(&mut self, col_index: usize) -> Result<(), CsvError> {
+        self.check_column(col_index, self.column_count())?;
+        let values = self
+            .headers
+            .iter()
+            .chain(&self.data)
+            .map(|row| row.get(col_index).cloned())
+            .collect();
+        self.commit(Edit::RemoveColumn {
+            col: col_index,
+            values,
+        });
+        Ok(())
+    }
+
+    /// Swaps two columns in the headers and every row. Rows too short to
+    /// have both are padded with empty cells first.
+    pub fn swap_columns(&mut self, a: usize, b: usize) -> Result<(), CsvError> {
+        let columns = self.column_count();
+        self.check_column(a, columns)?;
+        self.check_column(b, columns)?;
+        self.commit(Edit::SwapColumns {
+            a,
+            b,
+            lengths: self.row_lengths(),
+        });
+        Ok(())
+    }
+
+    /// Renames the column headed `name` to `new_name`.
+    pub fn rename_column(&mut self, name: &str, new_name: &str) -> Result<(), CsvError> {
+        let col_index = self
+            .column_index(name)
+            .ok_or_else(|| CsvError::UnknownColumn(name.to_string()))?;
+        self.commit(Edit::RenameColumn {
+            col: col_index,
+            old: name.to_string(),
+            new: new_name.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Sorts the data rows by `columns`, each a header or an index with the
+    /// direction to sort it in, e.g. `&[("Age", Order::Desc), ("Name",
+    /// Order::Asc)]`. Cells are compared as numbers, dates or text to suit
+    /// each column's contents; see [`Collation::Auto`](crate::Collation::Auto).
+    ///
+    /// The sort is stable and the header row stays first.
+    pub fn sort_by_columns(&mut self, columns: &[(&str, Order)]) -> Result<(), CsvError> {
+        let keys: Vec<SortKey> = columns
+            .iter()
+            .map(|&(column, order)| SortKey::new(column).order(order))
+            .collect();
+        self.sort_by_keys(&keys)
+    }
+
+    /// Sorts the data rows by `keys`, the first taking precedence. The sort
+    /// is stable and the header row stays first. Keys left to
+    /// [`Collation::Auto`](crate::Collation::Auto) compare cells as their
+    /// column's declared or inferred type, if it has one.
+    pub fn sort_by_keys(&mut self, keys: &[SortKey]) -> Result<(), CsvError> {
+        let order = sort::sorted_order(
+            self.headers.as_ref(),
+            &self.data,
+            self.column_types.as_deref(),
+            keys,
+        )?;
+        self.commit(Edit::Sort { order });
+        Ok(())
+    }
+
+    /// Every edit made since the data was parsed and not undone, oldest
+    /// first.
+    pub fn history(&self) -> &[Edit] {
+        &self.history
+    }
+
+    /// Takes back the most recent edit and returns it, or `None` if there
+    /// is nothing to undo.
+    pub fn undo(&mut self) -> Option<&Edit> {
+        let edit = self.history.pop()?;
+        edit.revert(&mut self.headers, &mut self.data);
+        self.retype(&edit);
+        self.undone.push(edit);
+        self.undone.last()
+    }
+
+    /// Makes the most recently undone edit again and returns it, or `None`
+    /// if there is nothing to redo. Any new edit clears what can be redone.
+    pub fn redo(&mut self) -> Option<&Edit> {
+        let edit = self.undone.pop()?;
+        edit.apply(&mut self.headers, &mut self.data);
+        self.retype(&edit);
+        self.history.push(edit);
+        self.history.last()
+    }
+
+    /// Starts staging edits to apply all together or not at all.
+    pub fn transaction(&mut self) -> Transaction<'_> {
+        Transaction::new(self)
+    }
+
+    /// Runs `edits`, and takes back everything it did if it fails, leaving
+    /// what can be redone and the column types as they were too.
+    pub(crate) fn atomically<F>(&mut self, edits: F) -> Result<(), CsvError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), CsvError>,
+    {
+        let applied = self.history.len();
+        let undone = self.undone.clone();
+        // Undoing an inserted or removed column forgets the types, so they
+        // are put back as they were rather than left to `undo`.
+        let column_types = self.column_types.clone();
+        let result = edits(self);
+        if result.is_err() {
+            while self.history.len() > applied {
+                self.undo();
+            }
+            self.undone = undone;
+            self.column_types = column_types;
+        }
+        result
+    }
+
+    fn commit(&mut self, edit: Edit) {
+        edit.apply(&mut self.headers, &mut self.data);
+        self.retype(&edit);
+        self.history.push(edit);
+        self.undone.clear();
+    }
+
+    /// Keeps the column types in line with the columns after `edit` is made
+    /// or taken back.
+    fn retype(&mut self, edit: &Edit) {
+        match edit {
+            Edit::SwapColumns { a, b, .. } => {
+                if let Some(types) = &mut self.column_types {
+                    if *a < types.len() && *b < types.len() {
+                        types.swap(*a, *b);
+                    } else {
+                        self.column_types = None;
+                    }
+                }
+            }
+            Edit::InsertColumn { .. } | Edit::RemoveColumn { .. } => self.column_types = None,
+            _ => {}
+        }
+    }
+
+    /// The length of the header row, if any, and of each data row.
+    fn row_lengths(&self) -> Vec<usize> {
+        self.headers
+            .iter()
+            .chain(&self.data)
+            .map(|row| row.len())
+            .collect()
+    }
+
+    fn check_row(&self, row_index: usize, limit: usize) -> Result<(), CsvError> {
+        if row_index < limit {
+            Ok(())
+        } else {
+            Err(CsvError::RowOutOfBounds {
+                row: row_index,
+                rows: self.data.len(),
+            })
+        }
+    }
+
+    fn check_column(&self, col_index: usize, limit: usize) -> Result<(), CsvError> {
+        if col_index < limit {
+            Ok(())
+        } else {
+            Err(CsvError::ColumnOutOfBounds {
+                col: col_index,
+                columns: self.column_count(),
+            })
+        }
+    }
+
+    /// The cell at `row_index` in the column headed `name`.
+    pub fn get_by_name(&self, row_index: usize, name: &str) -> Option<&str> {
+        self.get_cell(row_index, self.column_index(name)?)
+    }
+
+    /// Overwrites the cell at `row_index` in the column headed `name`.
+    pub fn update_by_name(
+        &mut self,
+        row_index: usize,
+        name: &str,
+        value: &str,
+    ) -> Result<(), CsvError> {
+        let col_index = self
+            .column_index(name)
+            .ok_or_else(|| CsvError::UnknownColumn(name.to_string()))?;
+        self.update_cell(row_index, col_index, value)
+    }
+
+    /// Writes the headers, if any, and every row to the file at `file_path`,
+    /// replacing it.
+    pub fn write_csv(&self, file_path: &str) -> Result<(), CsvError> {
+        let mut file = BufWriter::new(File::create(file_path)?);
+        self.write_to(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Writes the headers, if any, and every row to `sink`.
+    pub fn write_to<W: Write>(&self, mut sink: W) -> Result<(), CsvError> {
+        for row in self.headers.iter().chain(&self.data) {
+            writer::write_record(&mut sink, row, &self.dialect)?;
+        }
+        Ok(())
+    }
+
+    /// Deserializes every data row into a `T`. With headers, struct fields
+    /// are matched to columns by name; otherwise they are taken in order.
+    pub fn rows_as<T: DeserializeOwned>(&self) -> Result<Vec<T>, CsvError> {
+        let headers = self.headers.as_deref();
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(row_index, row)| {
+                de::from_row(headers, row).map_err(|err| CsvError::Deserialize {
+                    row: row_index,
+                    column: err
+                        .column
+                        .map(|col| match headers.and_then(|h| h.get(col)) {
+                            Some(name) => name.clone(),
+                            None => col.to_string(),
+                        }),
+                    message: err.message,
+                })
+            })
+            .collect()
+    }
+
+    /// Writes `records` to `sink` in this parser's dialect, preceded by a
+    /// header row of field names when the records are structs or maps.
+    pub fn write_records<T: Serialize, W: Write>(
+        &self,
+        mut sink: W,
+        records: &[T],
+    ) -> Result<(), CsvError> {
+        for (index, record) in records.iter().enumerate() {
+            let (names, row) = ser::to_row(record).map_err(|err| CsvError::Serialize {
+                record: index,
+                message: err.message,
+            })?;
+            if let (0, Some(names)) = (index, names) {
+                writer::write_record(&mut sink, &names, &self.dialect)?;
+            }
+            writer::write_record(&mut sink, &row, &self.dialect)?;
+        }
+        Ok(())
+    }
+
+    /// Renders the rows as a JSON array of objects keyed by header, or by
+    /// column index without headers, one object per line.
+    ///
+    /// Once [`CSVParser::column_types`] are known, cells are written as
+    /// numbers, booleans or nulls to suit their column, and dates as
+    /// strings; otherwise every cell is a string. Cells missing from short
+    /// rows are null. A repeated header name gets `_2`, `_3`, ... appended
+    /// so each key in an object is unique.
+    pub fn to_json(&self) -> String {
+        json::to_json(
+            self.headers.as_ref(),
+            &self.data,
+            self.column_types.as_deref(),
+            false,
+        )
+    }
+
+    /// Renders the rows as JSON Lines: one object per line, as in
+    /// [`CSVParser::to_json`].
+    pub fn to_jsonl(&self) -> String {
+        json::to_json(
+            self.headers.as_ref(),
+            &self.data,
+            self.column_types.as_deref(),
+            true,
+        )
+    }
+
+    /// Renders the headers, if any, and rows as an aligned text table.
+    pub fn to_table(&self, options: &TableOptions) -> String {
+        table::render_table(self.headers.as_deref(), &self.data, options)
+    }
+
+    /// Renders the headers, if any, and rows as a Markdown table; see
+    /// [`render_markdown`](crate::render_markdown).
+    pub fn to_markdown(&self) -> String {
+        markup::render_markdown(self.headers.as_deref(), &self.data)
+    }
+
+    /// Renders the headers, if any, and rows as an HTML table; see
+    /// [`render_html`](crate::render_html).
+    pub fn to_html(&self) -> String {
+        markup::render_html(self.headers.as_deref(), &self.data)
+    }
+
+    /// Renders the headers, if any, and rows as a LaTeX tabular; see
+    /// [`render_latex`](crate::render_latex).
+    pub fn to_latex(&self) -> String {
+        markup::render_latex(self.headers.as_deref(), &self.data)
+    }
+
+    /// Prints the headers, if any, and every row to stdout as a table.
+    pub fn display_csv(&self) -> Result<(), CsvError> {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(self.to_table(&TableOptions::default()).as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Finds `column` among the headers, or else reads it as an index below
+/// `columns`, so a header that looks like a number, such as `2020`, still
+/// names its column. This is how every method that takes a column name
+/// resolves it.
+pub fn resolve_column(
+    headers: Option<&Row>,
+    column: &str,
+    columns: usize,
+) -> Result<usize, CsvError> {
+    if let Some(col) =
+        headers.and_then(|headers| headers.iter().position(|header| header == column))
+    {
+        return Ok(col);
+    }
+    let col: usize = column
+        .parse()
+        .map_err(|_| CsvError::UnknownColumn(column.to_string()))?;
+    if col >= columns {
+        return Err(CsvError::ColumnOutOfBounds { col, columns });
+    }
+    Ok(col)
+}
+
+impl Default for CSVParser {
+    fn default() -> Self {
+        CSVParser::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{parser, row};
+
+    fn people() -> CSVParser {
+        parser("Name,Age\nAnn,31\nBob\n")
+    }
+
+    #[test]
+    fn keeps_the_header_row_apart() {
+        let parser = people();
+        assert_eq!(parser.headers(), Some(&row(&["Name", "Age"])));
+        assert_eq!(parser.get_row(0), Some(&row(&["Ann", "31"])));
+        let mut parser = CSVParser::new();
+        parser.parse_from("Name,Age\nAnn,31\n".as_bytes()).unwrap();
+        assert_eq!(parser.headers(), None);
+        assert_eq!(parser.get_cell(0, 0), Some("Name"));
+    }
+
+    #[test]
+    fn appends_every_source_parsed() {
+        let mut parser = people();
+        parser.parse_from("Name,Age\nCid,40\n".as_bytes()).unwrap();
+        assert_eq!(parser.get_by_name(2, "Name"), Some("Cid"));
+    }
+
+    #[test]
+    fn keeps_its_contents_when_a_parse_fails() {
+        let mut parser = people();
+        parser.update_cell(0, 1, "32").unwrap();
+        let err = parser
+            .parse_from("Id,Code\nCid,\"40\n".as_bytes())
+            .unwrap_err();
+        assert!(matches!(err, CsvError::UnterminatedQuote { .. }));
+        assert_eq!(parser.headers(), Some(&row(&["Name", "Age"])));
+        assert_eq!(parser.len(), 2);
+        assert_eq!(parser.history().len(), 1);
+    }
+
+    #[test]
+    fn sets_aside_ragged_rows_when_quarantining() {
+        let mut parser = CSVParser::new()
+            .has_headers(true)
+            .ragged_rows(RaggedRows::Quarantine);
+        parser
+            .parse_from("Name,Age\nAnn,31\nBob\n".as_bytes())
+            .unwrap();
+        assert_eq!(parser.len(), 1);
+        let quarantined = parser.quarantined();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].position.line, 3);
+        assert_eq!(quarantined[0].row, ["Bob"]);
+    }
+
+    #[test]
+    fn writes_headers_and_rows_in_its_dialect() {
+        let mut parser = CSVParser::new()
+            .has_headers(true)
+            .with_dialect(Dialect::tsv());
+        parser
+            .parse_from("a\tb\n1\tx y\n2\t\"q\"\"\"\n".as_bytes())
+            .unwrap();
+        let mut out = Vec::new();
+        parser.write_to(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "a\tb\n1\tx y\n2\t\"q\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn finds_columns_by_header() {
+        let parser = people();
+        assert_eq!(parser.column_index("Age"), Some(1));
+        assert_eq!(parser.column_index("age"), None);
+        assert_eq!(parser.get_by_name(0, "Age"), Some("31"));
+        assert_eq!(parser.get_by_name(1, "Age"), None);
+        assert_eq!(CSVParser::new().column_index("Age"), None);
+    }
+
+    #[test]
+    fn updates_cells_by_header() {
+        let mut parser = people();
+        parser.update_by_name(0, "Name", "Amy").unwrap();
+        assert_eq!(parser.get_row(0), Some(&row(&["Amy", "31"])));
+        let err = parser.update_by_name(0, "City", "Rome").unwrap_err();
+        assert!(matches!(err, CsvError::UnknownColumn(name) if name == "City"));
+    }
+
+    #[test]
+    fn reports_which_index_is_out_of_bounds() {
+        let mut parser = people();
+        let err = parser.update_cell(2, 0, "Cid").unwrap_err();
+        assert!(matches!(err, CsvError::RowOutOfBounds { row: 2, rows: 2 }));
+        let err = parser.update_cell(1, 1, "40").unwrap_err();
+        assert!(matches!(
+            err,
+            CsvError::ColumnOutOfBounds { col: 1, columns: 1 }
+        ));
+    }
+
+    #[test]
+    fn inserts_and_removes_rows() {
+        let mut parser = people();
+        parser.insert_row(0, row(&["Cid", "40"])).unwrap();
+        parser.insert_row(3, row(&["Dan", "22"])).unwrap();
+        assert_eq!(parser.get_by_name(0, "Name"), Some("Cid"));
+        assert_eq!(parser.get_by_name(3, "Name"), Some("Dan"));
+        assert_eq!(parser.remove_row(1).unwrap(), row(&["Ann", "31"]));
+        assert_eq!(parser.len(), 3);
+        let err = parser.insert_row(5, Row::new()).unwrap_err();
+        assert!(matches!(err, CsvError::RowOutOfBounds { row: 5, rows: 3 }));
+        assert!(parser.remove_row(3).is_err());
+    }
+
+    #[test]
+    fn inserts_a_computed_column_padding_short_rows() {
+        let mut parser = people();
+        parser
+            .insert_column(2, "Adult", |row| {
+                let age: u32 = row.get(1).and_then(|age| age.parse().ok()).unwrap_or(0);
+                (age >= 18).to_string()
+            })
+            .unwrap();
+        assert_eq!(parser.headers(), Some(&row(&["Name", "Age", "Adult"])));
+        assert_eq!(parser.get_row(0), Some(&row(&["Ann", "31", "true"])));
+        assert_eq!(parser.get_row(1), Some(&row(&["Bob", "", "false"])));
+        let err = parser
+            .insert_column(4, "City", |_| String::new())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CsvError::ColumnOutOfBounds { col: 4, columns: 3 }
+        ));
+    }
+
+    #[test]
+    fn removes_a_column_short_rows_lack() {
+        let mut parser = people();
+        parser.remove_column(1).unwrap();
+        assert_eq!(parser.headers(), Some(&row(&["Name"])));
+        assert_eq!(parser.get_row(0), Some(&row(&["Ann"])));
+        assert_eq!(parser.get_row(1), Some(&row(&["Bob"])));
+        assert!(parser.remove_column(1).is_err());
+    }
+
+    #[test]
+    fn swaps_and_renames_columns() {
+        let mut parser = people();
+        parser.swap_columns(0, 1).unwrap();
+        assert_eq!(parser.headers(), Some(&row(&["Age", "Name"])));
+        assert_eq!(parser.get_row(1), Some(&row(&["", "Bob"])));
+        parser.rename_column("Age", "Years").unwrap();
+        assert_eq!(parser.column_index("Years"), Some(0));
+        let err = parser.rename_column("Age", "Years").unwrap_err();
+        assert!(matches!(err, CsvError::UnknownColumn(name) if name == "Age"));
+    }
+
+    #[test]
+    fn resolves_a_header_before_an_index() {
+        let headers = row(&["2019", "2020", "Name"]);
+        assert_eq!(resolve_column(Some(&headers), "2020", 3).unwrap(), 1);
+        assert_eq!(resolve_column(Some(&headers), "0", 3).unwrap(), 0);
+        assert_eq!(resolve_column(None, "2", 3).unwrap(), 2);
+        let err = resolve_column(Some(&headers), "3", 3).unwrap_err();
+        assert!(matches!(
+            err,
+            CsvError::ColumnOutOfBounds { col: 3, columns: 3 }
+        ));
+        let err = resolve_column(None, "Name", 3).unwrap_err();
+        assert!(matches!(err, CsvError::UnknownColumn(name) if name == "Name"));
+    }
+
+    #[derive(Debug, serde::Deserialize, serde::Serialize, PartialEq)]
+    struct Person {
+        #[serde(rename = "Name")]
+        name: String,
+        #[serde(rename = "Age")]
+        age: Option<u32>,
+    }
+
+    #[test]
+    fn reads_rows_as_records() {
+        let people: Vec<Person> = people().rows_as().unwrap();
+        assert_eq!(people[0].age, Some(31));
+        assert_eq!(people[1].name, "Bob");
+        assert_eq!(people[1].age, None);
+    }
+
+    #[test]
+    fn names_the_row_and_column_a_record_failed_on() {
+        let mut parser = people();
+        parser.update_cell(0, 1, "old").unwrap();
+        let err = parser.rows_as::<Person>().unwrap_err();
+        let CsvError::Deserialize { row, column, .. } = err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert_eq!((row, column.as_deref()), (0, Some("Age")));
+    }
+
+    #[test]
+    fn writes_records_under_one_header_row() {
+        let records = [
+            Person {
+                name: "Ann".to_string(),
+                age: Some(31),
+            },
+            Person {
+                name: "Bob, Jr.".to_string(),
+                age: None,
+            },
+        ];
+        let mut out = Vec::new();
+        CSVParser::new().write_records(&mut out, &records).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Name,Age\nAnn,31\n\"Bob, Jr.\",\n"
+        );
+    }
+}
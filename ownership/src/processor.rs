@@ -0,0 +1,34 @@
+use crate::error::CsvError;
+use crate::parser::CSVParser;
+
+/// Runs a fixed read, inspect, update and write script against a borrowed
+/// [`CSVParser`].
+pub struct CSVProcessor<'a> {
+    parser: &'a mut CSVParser,
+}
+
+impl<'a> CSVProcessor<'a> {
+    /// Borrows `parser` mutably for as long as the processor lives.
+    pub fn new(parser: &'a mut CSVParser) -> Self {
+        CSVProcessor { parser }
+    }
+
+    /// Parses `input_path`, prints row 1 and cell (2, 3), overwrites that
+    /// cell, writes the result to `output_path` and prints it.
+    pub fn process_csv(&mut self, input_path: &str, output_path: &str) -> Result<(), CsvError> {
+        self.parser.parse_csv(input_path)?;
+
+        if let Some(row) = self.parser.get_row(1) {
+            println!("Row 1: {:?}", row);
+        }
+        if let Some(cell) = self.parser.get_cell(2, 3) {
+            println!("Cell (2, 3): {}", cell);
+        }
+
+        self.parser.update_cell(2, 3, "Updated Value")?;
+        self.parser.write_csv(output_path)?;
+        self.parser.display_csv()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,524 @@
+use std::io::{BufRead, BufReader, Read};
+
+use crate::dialect::{Dialect, Escape};
+use crate::error::{CsvError, Position};
+use crate::Row;
+
+enum State {
+    Start,
+    Unquoted,
+    Quoted,
+    QuoteInQuoted,
+    EscapeInUnquoted,
+    EscapeInQuoted,
+}
+
+/// What a reader does with a record whose field count differs from the
+/// header row's or, without headers, the first record's.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RaggedRows {
+    /// Keeps the record as it is.
+    #[default]
+    Allow,
+    /// Fails with [`CsvError::RaggedRow`].
+    Error,
+    /// Adds empty fields to short records; long ones are kept as they are.
+    Pad,
+    /// Drops extra fields from long records; short ones are kept as they are.
+    Truncate,
+    /// Pads short records and truncates long ones.
+    Fit,
+    /// Sets the record aside, with where it starts, to be taken with
+    /// [`CsvReader::take_quarantined`] or looked at with
+    /// [`CSVParser::quarantined`](crate::CSVParser::quarantined).
+    Quarantine,
+}
+
+/// A record set aside by [`RaggedRows::Quarantine`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quarantined {
+    /// Where the record starts.
+    pub position: Position,
+    /// The record as it was read.
+    pub row: Row,
+}
+
+/// Reads records one at a time from any [`Read`] source.
+///
+/// Only the record being parsed is held in memory, so inputs of any size can
+/// be processed. Iterating yields owned rows; [`CsvReader::read_record`]
+/// refills a caller-provided row instead, reusing its allocations.
+pub struct CsvReader<R> {
+    reader: BufReader<R>,
+    dialect: Dialect,
+    has_headers: bool,
+    headers: Option<Row>,
+    ragged: RaggedRows,
+    // How many fields every record should have, once known.
+    width: Option<usize>,
+    quarantined: Vec<Quarantined>,
+    buf: Vec<u8>,
+    // Where the next physical line starts.
+    next_line: Position,
+    // Where the most recently read record starts.
+    record_start: Position,
+}
+
+impl<R: Read> CsvReader<R> {
+    /// Reads RFC 4180 CSV without headers from `reader`.
+    pub fn new(reader: R) -> Self {
+        CsvReader {
+            reader: BufReader::new(reader),
+            dialect: Dialect::default(),
+            has_headers: false,
+            headers: None,
+            ragged: RaggedRows::Allow,
+            width: None,
+            quarantined: Vec::new(),
+            buf: Vec::new(),
+            next_line: Position::default(),
+            record_start: Position::default(),
+        }
+    }
+
+    /// Sets the dialect records are read in.
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// When set, the first record is read as the header row and is not
+    /// yielded as data.
+    pub fn has_headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// Sets what is done with records that have too few or too many fields.
+    pub fn ragged_rows(mut self, ragged: RaggedRows) -> Self {
+        self.ragged = ragged;
+        self
+    }
+
+    /// Returns the header row, reading it first if no record has been read
+    /// yet. Always `None` unless `has_headers` was set.
+    pub fn headers(&mut self) -> Result<Option<&Row>, CsvError> {
+        if self.has_headers && self.headers.is_none() {
+            let mut headers = Row::new();
+            if self.read_raw(&mut headers)? {
+                self.width = Some(headers.len());
+                self.headers = Some(headers);
+            }
+        }
+        Ok(self.headers.as_ref())
+    }
+
+    /// Hands over the records set aside by [`RaggedRows::Quarantine`] since
+    /// this was last called, in the order they were read.
+    pub fn take_quarantined(&mut self) -> Vec<Quarantined> {
+        std::mem::take(&mut self.quarantined)
+    }
+
+    /// Where the most recently read record starts.
+    pub fn position(&self) -> Position {
+        self.record_start
+    }
+
+    /// Reads the next data record into `record`, reusing its strings.
+    /// Returns `false` once the input is exhausted.
+    pub fn read_record(&mut self, record: &mut Row) -> Result<bool, CsvError> {
+        self.headers()?;
+        loop {
+            if !self.read_raw(record)? {
+                return Ok(false);
+            }
+            let expected = *self.width.get_or_insert(record.len());
+            let found = record.len();
+            match self.ragged {
+                _ if found == expected => {}
+                RaggedRows::Allow => {}
+                RaggedRows::Error => {
+                    return Err(CsvError::RaggedRow {
+                        position: self.record_start,
+                        expected,
+                        found,
+                    })
+                }
+                RaggedRows::Pad if found < expected => record.resize(expected, String::new()),
+                RaggedRows::Truncate if found > expected => record.truncate(expected),
+                RaggedRows::Pad | RaggedRows::Truncate => {}
+                RaggedRows::Fit => record.resize(expected, String::new()),
+                RaggedRows::Quarantine => {
+                    self.quarantined.push(Quarantined {
+                        position: self.record_start,
+                        row: record.clone(),
+                    });
+                    continue;
+                }
+            }
+            return Ok(true);
+        }
+    }
+
+    /// Reads the next record according to the dialect.
+    ///
+    /// Quoted fields may contain delimiters, escaped quotes and line breaks;
+    /// a record therefore spans as many physical lines as it takes to close
+    /// its last quote. Blank lines and comment lines between records are
+    /// skipped.
+    fn read_raw(&mut self, record: &mut Row) -> Result<bool, CsvError> {
+        let dialect = self.dialect;
+        // Index of the field being filled in `record`.
+        let mut field = 0;
+        let mut state = State::Start;
+        let mut quote_start = self.next_line;
+        start_field(record, field);
+
+        loop {
+            self.buf.clear();
+            if self.reader.read_until(b'\n', &mut self.buf)? == 0 {
+                return match state {
+                    State::Start => {
+                        record.clear();
+                        Ok(false)
+                    }
+                    State::Quoted => Err(CsvError::UnterminatedQuote {
+                        position: quote_start,
+                    }),
+                    _ => {
+                        record.truncate(field + 1);
+                        Ok(true)
+                    }
+                };
+            }
+
+            let line_start = self.next_line;
+            self.next_line.line += 1;
+            self.next_line.byte += self.buf.len() as u64;
+            let line = decode(&self.buf, line_start)?;
+
+            let (content, terminator) = split_terminator(line);
+            if matches!(state, State::Start) && field == 0 {
+                if content.is_empty() || is_comment(content, &dialect) {
+                    continue;
+                }
+                self.record_start = line_start;
+            }
+
+            for (column, (offset, c)) in content.char_indices().enumerate() {
+                state = match state {
+                    State::Start | State::Unquoted if c == dialect.delimiter => {
+                        field += 1;
+                        start_field(record, field);
+                        State::Start
+                    }
+                    State::Start if c == dialect.quote => {
+                        quote_start = Position {
+                            line: line_start.line,
+                            column: column as u64 + 1,
+                            byte: line_start.byte + offset as u64,
+                        };
+                        State::Quoted
+                    }
+                    State::Start | State::Unquoted if dialect.is_escape(c) => {
+                        State::EscapeInUnquoted
+                    }
+                    State::Start | State::Unquoted => {
+                        record[field].push(c);
+                        State::Unquoted
+                    }
+                    State::Quoted if dialect.is_escape(c) => State::EscapeInQuoted,
+                    State::Quoted if c == dialect.quote => State::QuoteInQuoted,
+                    State::Quoted => {
+                        record[field].push(c);
+                        State::Quoted
+                    }
+                    State::QuoteInQuoted
+                        if c == dialect.quote && dialect.escape == Escape::Doubled =>
+                    {
+                        record[field].push(c);
+                        State::Quoted
+                    }
+                    State::QuoteInQuoted if c == dialect.delimiter => {
+                        field += 1;
+                        start_field(record, field);
+                        State::Start
+                    }
+                    // Text after a closing quote is not valid RFC 4180, but
+                    // being lenient here matches what spreadsheet tools produce.
+                    State::QuoteInQuoted => {
+                        record[field].push(c);
+                        State::Unquoted
+                    }
+                    State::EscapeInUnquoted => {
+                        record[field].push(c);
+                        State::Unquoted
+                    }
+                    State::EscapeInQuoted => {
+                        record[field].push(c);
+                        State::Quoted
+                    }
+                };
+            }
+
+            // An open quote or a trailing escape carries the line break into
+            // the field and the record on to the next physical line.
+            state = match state {
+                State::Quoted | State::EscapeInQuoted => State::Quoted,
+                State::EscapeInUnquoted => State::Unquoted,
+                _ => {
+                    record.truncate(field + 1);
+                    return Ok(true);
+                }
+            };
+            record[field].push_str(terminator);
+        }
+    }
+}
+
+impl<R: Read> Iterator for CsvReader<R> {
+    type Item = Result<Row, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = Row::new();
+        match self.read_record(&mut record) {
+            Ok(true) => Some(Ok(record)),
+            Ok(false) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// Makes `record[index]` an empty field, reusing an existing string if there
+/// is one. Fields are started in order, so `index` is at most `record.len()`.
+fn start_field(record: &mut Row, index: usize) {
+    match record.get_mut(index) {
+        Some(field) => field.clear(),
+        None => record.push(String::new()),
+    }
+}
+
+fn decode(buf: &[u8], line_start: Position) -> Result<&str, CsvError> {
+    std::str::from_utf8(buf).map_err(|err| {
+        let valid = String::from_utf8_lossy(&buf[..err.valid_up_to()]);
+        CsvError::InvalidUtf8 {
+            position: Position {
+                line: line_start.line,
+                column: valid.chars().count() as u64 + 1,
+                byte: line_start.byte + err.valid_up_to() as u64,
+            },
+        }
+    })
+}
+
+fn is_comment(line: &str, dialect: &Dialect) -> bool {
+    dialect
+        .comment
+        .is_some_and(|prefix| line.starts_with(prefix))
+}
+
+fn split_terminator(line: &str) -> (&str, &str) {
+    if let Some(content) = line.strip_suffix("\r\n") {
+        (content, "\r\n")
+    } else if let Some(content) = line.strip_suffix('\n') {
+        (content, "\n")
+    } else {
+        (line, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{row, rows};
+
+    fn read(input: &str, dialect: &Dialect) -> Result<Vec<Row>, CsvError> {
+        read_bytes(input.as_bytes(), dialect)
+    }
+
+    fn read_bytes(input: &[u8], dialect: &Dialect) -> Result<Vec<Row>, CsvError> {
+        CsvReader::new(input).with_dialect(*dialect).collect()
+    }
+
+    #[test]
+    fn reads_quoted_fields() {
+        let records = read("a,\"b,c\",\"\"\n\"x\"y,,z\n", &Dialect::default()).unwrap();
+        assert_eq!(records, rows(&[&["a", "b,c", ""], &["xy", "", "z"]]));
+    }
+
+    #[test]
+    fn reads_doubled_quotes() {
+        let records = read("\"He said \"\"hi\"\"\",x\n", &Dialect::default()).unwrap();
+        assert_eq!(records, rows(&[&["He said \"hi\"", "x"]]));
+    }
+
+    #[test]
+    fn keeps_line_breaks_in_quoted_fields() {
+        let records = read("\"one\ntwo\r\nthree\",x\nnext,y\n", &Dialect::default()).unwrap();
+        assert_eq!(
+            records,
+            rows(&[&["one\ntwo\r\nthree", "x"], &["next", "y"]])
+        );
+    }
+
+    #[test]
+    fn skips_blank_lines_and_reads_crlf() {
+        let records = read("a,b\r\n\r\nc,d\r\n\ne,f", &Dialect::default()).unwrap();
+        assert_eq!(records, rows(&[&["a", "b"], &["c", "d"], &["e", "f"]]));
+    }
+
+    #[test]
+    fn reports_where_an_unterminated_quote_opens() {
+        let err = read("a,b\nc,\"d\ne\n", &Dialect::default()).unwrap_err();
+        let CsvError::UnterminatedQuote { position } = err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        let expected = Position {
+            line: 2,
+            column: 3,
+            byte: 6,
+        };
+        assert_eq!(position, expected);
+    }
+
+    #[test]
+    fn reports_where_invalid_utf8_is() {
+        let err = read_bytes(b"ab\n\xc3\xa9,\xff\n", &Dialect::default()).unwrap_err();
+        let CsvError::InvalidUtf8 { position } = err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        // Columns count characters, and `é` is two bytes.
+        let expected = Position {
+            line: 2,
+            column: 3,
+            byte: 6,
+        };
+        assert_eq!(position, expected);
+    }
+
+    #[test]
+    fn tracks_where_each_record_starts() {
+        let input = "\"a\nb\",c\r\n\n# d\ne,f\n";
+        let mut reader =
+            CsvReader::new(input.as_bytes()).with_dialect(Dialect::default().comment('#'));
+        assert_eq!(reader.next().unwrap().unwrap(), ["a\nb", "c"]);
+        assert_eq!(reader.position(), Position::default());
+        assert_eq!(reader.next().unwrap().unwrap(), ["e", "f"]);
+        let expected = Position {
+            line: 5,
+            column: 1,
+            byte: 14,
+        };
+        assert_eq!(reader.position(), expected);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn reads_the_header_row_apart() {
+        let mut reader = CsvReader::new("a,b\n1,2\n".as_bytes()).has_headers(true);
+        assert_eq!(reader.headers().unwrap(), Some(&row(&["a", "b"])));
+        let records: Vec<Row> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(records, rows(&[&["1", "2"]]));
+        assert_eq!(reader.headers().unwrap(), Some(&row(&["a", "b"])));
+        let mut reader = CsvReader::new("a,b\n".as_bytes());
+        assert_eq!(reader.headers().unwrap(), None);
+    }
+
+    #[test]
+    fn refills_a_record_in_place() {
+        let mut reader = CsvReader::new("a,b,c\nd\n".as_bytes());
+        let mut record = Row::new();
+        assert!(reader.read_record(&mut record).unwrap());
+        assert_eq!(record, ["a", "b", "c"]);
+        assert!(reader.read_record(&mut record).unwrap());
+        assert_eq!(record, ["d"]);
+        assert!(!reader.read_record(&mut record).unwrap());
+    }
+
+    #[test]
+    fn reads_backslash_escapes() {
+        let dialect = Dialect::default().escape(Escape::Char('\\'));
+        let records = read("\"He said \\\"hi\\\"\",a\\,b,c\\\\d\n", &dialect).unwrap();
+        assert_eq!(records, rows(&[&["He said \"hi\"", "a,b", "c\\d"]]));
+    }
+
+    #[test]
+    fn escapes_a_line_break_outside_quotes() {
+        let dialect = Dialect::default().escape(Escape::Char('\\'));
+        let records = read("a\\\nb,c\n", &dialect).unwrap();
+        assert_eq!(records, rows(&[&["a\nb", "c"]]));
+    }
+
+    #[test]
+    fn reads_other_delimiters_and_quotes() {
+        let records = read("a\t'b\tc'\td\n", &Dialect::tsv().quote('\'')).unwrap();
+        assert_eq!(records, rows(&[&["a", "b\tc", "d"]]));
+        let records = read("a;\"b;c\"\n", &Dialect::default().delimiter(';')).unwrap();
+        assert_eq!(records, rows(&[&["a", "b;c"]]));
+    }
+
+    #[test]
+    fn skips_comment_lines() {
+        let dialect = Dialect::default().comment('#');
+        let records = read("# note\na,b\n#x,y\n\"#z\",w\n", &dialect).unwrap();
+        assert_eq!(records, rows(&[&["a", "b"], &["#z", "w"]]));
+    }
+
+    #[test]
+    fn pads_and_truncates_ragged_rows() {
+        let read = |ragged| {
+            CsvReader::new("a,b\n1\n2,3,4\n".as_bytes())
+                .ragged_rows(ragged)
+                .collect::<Result<Vec<Row>, _>>()
+                .unwrap()
+        };
+        assert_eq!(
+            read(RaggedRows::Allow),
+            rows(&[&["a", "b"], &["1"], &["2", "3", "4"]])
+        );
+        assert_eq!(
+            read(RaggedRows::Pad),
+            rows(&[&["a", "b"], &["1", ""], &["2", "3", "4"]])
+        );
+        assert_eq!(
+            read(RaggedRows::Truncate),
+            rows(&[&["a", "b"], &["1"], &["2", "3"]])
+        );
+        assert_eq!(
+            read(RaggedRows::Fit),
+            rows(&[&["a", "b"], &["1", ""], &["2", "3"]])
+        );
+    }
+
+    #[test]
+    fn reports_where_a_ragged_row_starts() {
+        let err = CsvReader::new("a,b\nc,d\ne\n".as_bytes())
+            .ragged_rows(RaggedRows::Error)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err();
+        let CsvError::RaggedRow {
+            position,
+            expected,
+            found,
+        } = err
+        else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert_eq!((position.line, position.byte), (3, 8));
+        assert_eq!((expected, found), (2, 1));
+    }
+
+    #[test]
+    fn quarantines_ragged_rows() {
+        let mut reader = CsvReader::new("a,b,c\n1,2\n3,4,5\n".as_bytes())
+            .has_headers(true)
+            .ragged_rows(RaggedRows::Quarantine);
+        let records: Vec<Row> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(records, rows(&[&["3", "4", "5"]]));
+        let quarantined = reader.take_quarantined();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].position.line, 2);
+        assert_eq!(quarantined[0].row, ["1", "2"]);
+        assert!(reader.take_quarantined().is_empty());
+    }
+}
@@ -0,0 +1,588 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::error::CsvError;
+use crate::parser::resolve_column;
+use crate::value::{self, ColumnType, Value};
+use crate::Row;
+
+/// What a file's columns must look like, for
+/// [`CSVParser::validate`](crate::CSVParser::validate).
+///
+/// A schema is built in code or read from TOML or JSON with one table per
+/// column, as in:
+///
+/// ```toml
+/// [[columns]]
+/// name = "Age"
+/// type = "integer"
+/// nullable = false
+/// min = 0
+/// max = 150
+///
+/// [[columns]]
+/// name = "City"
+/// allowed = ["London", "New York", "Paris"]
+/// ```
+///
+/// Columns the schema does not mention are not checked.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    /// The rules, one per column.
+    #[serde(default)]
+    pub columns: Vec<ColumnRule>,
+}
+
+/// The rules for one column of a [`Schema`]. Only the name is needed; every
+/// other check is skipped unless set.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnRule {
+    /// The column's header, or its index.
+    pub name: String,
+    /// Whether the column must be present. Defaults to `true`.
+    #[serde(default = "yes")]
+    pub required: bool,
+    /// The type every non-null cell must have.
+    #[serde(rename = "type")]
+    pub column_type: Option<ColumnType>,
+    /// Whether cells may be null, as [`Value::Null`] is. Defaults to `true`.
+    #[serde(default = "yes")]
+    pub nullable: bool,
+    /// A regular expression every non-null cell must match in full.
+    pub pattern: Option<String>,
+    /// The smallest number allowed.
+    pub min: Option<f64>,
+    /// The largest number allowed.
+    pub max: Option<f64>,
+    /// The only values allowed.
+    pub allowed: Option<Vec<String>>,
+    /// Whether no two non-null cells may be the same.
+    #[serde(default)]
+    pub unique: bool,
+}
+
+fn yes() -> bool {
+    true
+}
+
+impl Schema {
+    /// A schema with no rules, which every file meets.
+    pub fn new() -> Self {
+        Schema::default()
+    }
+
+    /// Adds the rules for one column.
+    pub fn column(mut self, rule: ColumnRule) -> Self {
+        self.columns.push(rule);
+        self
+    }
+
+    /// Reads a schema from TOML.
+    pub fn from_toml(text: &str) -> Result<Self, CsvError> {
+        toml::from_str(text).map_err(|err| CsvError::InvalidSchema {
+            message: err.to_string().trim_end().to_string(),
+        })
+    }
+
+    /// Reads a schema from JSON.
+    pub fn from_json(text: &str) -> Result<Self, CsvError> {
+        serde_json::from_str(text).map_err(|err| CsvError::InvalidSchema {
+            message: err.to_string(),
+        })
+    }
+
+    /// Reads a schema from a file: JSON if its name ends in `.json`, and TOML
+    /// otherwise.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, CsvError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => Schema::from_json(&text),
+            _ => Schema::from_toml(&text),
+        }
+    }
+}
+
+impl ColumnRule {
+    /// A required, nullable column of any type, headed `name` or at index
+    /// `name`.
+    pub fn new(name: &str) -> Self {
+        ColumnRule {
+            name: name.to_string(),
+            required: true,
+            column_type: None,
+            nullable: true,
+            pattern: None,
+            min: None,
+            max: None,
+            allowed: None,
+            unique: false,
+        }
+    }
+
+    /// Sets whether the column must be present.
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Sets the type every non-null cell must have.
+    pub fn column_type(mut self, column_type: ColumnType) -> Self {
+        self.column_type = Some(column_type);
+        self
+    }
+
+    /// Sets whether cells may be null.
+    pub fn nullable(mut self, nullable: bool) -> Self {
+        self.nullable = nullable;
+        self
+    }
+
+    /// Sets a regular expression every non-null cell must match in full.
+    pub fn pattern(mut self, pattern: &str) -> Self {
+        self.pattern = Some(pattern.to_string());
+        self
+    }
+
+    /// Sets the smallest number allowed.
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Sets the largest number allowed.
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Sets the only values allowed.
+    pub fn allowed(mut self, values: &[&str]) -> Self {
+        self.allowed = Some(values.iter().map(|value| value.to_string()).collect());
+        self
+    }
+
+    /// Sets whether no two non-null cells may be the same.
+    pub fn unique(mut self, unique: bool) -> Self {
+        self.unique = unique;
+        self
+    }
+}
+
+/// Every way a file broke a [`Schema`], as returned by
+/// [`CSVParser::validate`](crate::CSVParser::validate).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    /// How many data rows were checked.
+    pub rows: usize,
+    /// What was wrong, column by column in schema order, and row by row
+    /// within each column.
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Whether the file met the schema.
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// One violation per line.
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for violation in &self.violations {
+            writeln!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+/// One way a file broke a [`Schema`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// The data row, or `None` if the whole column is at fault.
+    pub row: Option<usize>,
+    /// The column, as the schema names it.
+    pub column: String,
+    /// The offending cell, empty if the whole column is at fault.
+    pub value: String,
+    /// What was wrong.
+    pub problem: Problem,
+}
+
+/// What was wrong in a [`Violation`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    /// A required column is not in the file.
+    MissingColumn,
+    /// A cell in a column that is not nullable is null.
+    Null,
+    /// A cell is not of the column's type.
+    WrongType(ColumnType),
+    /// A cell does not match the column's pattern.
+    NoMatch(String),
+    /// A cell in a column with a range is not a number.
+    NotANumber,
+    /// A cell is outside the column's range.
+    OutOfRange {
+        /// The smallest number allowed.
+        min: Option<f64>,
+        /// The largest number allowed.
+        max: Option<f64>,
+    },
+    /// A cell is not one of the column's allowed values.
+    NotAllowed,
+    /// A cell in a unique column repeats an earlier one.
+    Duplicate {
+        /// The data row it first appears in.
+        first_row: usize,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(row) = self.row {
+            write!(f, "row {}, ", row)?;
+        }
+        write!(f, "column {}: ", self.column)?;
+        let value = &self.value;
+        match &self.problem {
+            Problem::MissingColumn => write!(f, "missing"),
+            Problem::Null => write!(f, "null, but the column is not nullable"),
+            Problem::WrongType(column_type) => {
+                write!(f, "expected {}, found {:?}", column_type, value)
+            }
+            Problem::NoMatch(pattern) => write!(f, "{:?} does not match {}", value, pattern),
+            Problem::NotANumber => write!(f, "{:?} is not a number", value),
+            Problem::OutOfRange { min, max } => {
+                write!(f, "{:?} is out of range", value)?;
+                match (min, max) {
+                    (Some(min), Some(max)) => write!(f, ", expected {} to {}", min, max),
+                    (Some(min), None) => write!(f, ", expected at least {}", min),
+                    (None, Some(max)) => write!(f, ", expected at most {}", max),
+                    (None, None) => Ok(()),
+                }
+            }
+            Problem::NotAllowed => write!(f, "{:?} is not an allowed value", value),
+            Problem::Duplicate { first_row } => {
+                write!(f, "{:?} already appears in row {}", value, first_row)
+            }
+        }
+    }
+}
+
+/// Checks `data` against `schema`; see
+/// [`CSVParser::validate`](crate::CSVParser::validate).
+pub(crate) fn validate(
+    schema: &Schema,
+    headers: Option<&Row>,
+    data: &[Row],
+) -> Result<ValidationReport, CsvError> {
+    let mut report = ValidationReport {
+        rows: data.len(),
+        violations: Vec::new(),
+    };
+    let columns = headers
+        .into_iter()
+        .chain(data)
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0);
+    for rule in &schema.columns {
+        let pattern = rule
+            .pattern
+            .as_ref()
+            .map(|pattern| {
+                // Checked as written first, so errors do not show the anchors.
+                Regex::new(pattern)
+                    .and_then(|_| Regex::new(&format!("^(?:{})$", pattern)))
+                    .map_err(|err| CsvError::InvalidSchema {
+                        message: format!("column {}: {}", rule.name, err),
+                    })
+            })
+            .transpose()?;
+        let col = match resolve_column(headers, &rule.name, columns) {
+            Ok(col) => col,
+            Err(_) => {
+                if rule.required {
+                    report.violations.push(Violation {
+                        row: None,
+                        column: rule.name.clone(),
+                        value: String::new(),
+                        problem: Problem::MissingColumn,
+                    });
+                }
+                continue;
+            }
+        };
+
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for (row_index, row) in data.iter().enumerate() {
+            let cell = row.get(col).map_or("", String::as_str);
+            let problem = check(rule, pattern.as_ref(), cell).or_else(|| {
+                if !rule.unique || value::is_null(cell.trim()) {
+                    return None;
+                }
+                let first_row = *seen.entry(cell.trim()).or_insert(row_index);
+                (first_row != row_index).then_some(Problem::Duplicate { first_row })
+            });
+            if let Some(problem) = problem {
+                report.violations.push(Violation {
+                    row: Some(row_index),
+                    column: rule.name.clone(),
+                    value: cell.to_string(),
+                    problem,
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// The first rule `cell` breaks, uniqueness aside.
+fn check(rule: &ColumnRule, pattern: Option<&Regex>, cell: &str) -> Option<Problem> {
+    let trimmed = cell.trim();
+    if value::is_null(trimmed) {
+        return (!rule.nullable).then_some(Problem::Null);
+    }
+    if let Some(column_type) = rule.column_type {
+        if Value::parse_as(cell, column_type).is_none() {
+            return Some(Problem::WrongType(column_type));
+        }
+    }
+    if let (Some(pattern), Some(source)) = (pattern, &rule.pattern) {
+        if !pattern.is_match(trimmed) {
+            return Some(Problem::NoMatch(source.clone()));
+        }
+    }
+    if rule.min.is_some() || rule.max.is_some() {
+        let Some(Value::Float(number)) = Value::parse_as(cell, ColumnType::Float) else {
+            return Some(Problem::NotANumber);
+        };
+        if rule.min.is_some_and(|min| number < min) || rule.max.is_some_and(|max| number > max) {
+            return Some(Problem::OutOfRange {
+                min: rule.min,
+                max: rule.max,
+            });
+        }
+    }
+    if let Some(allowed) = &rule.allowed {
+        if !allowed.iter().any(|value| value == trimmed) {
+            return Some(Problem::NotAllowed);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parser;
+
+    const PEOPLE: &str = "Name,Age,City\n\
+                          Ann,31,London\n\
+                          Bob,,Paris\n\
+                          Ann,151,Tokyo\n";
+
+    fn problems(schema: &Schema, text: &str) -> Vec<(Option<usize>, Problem)> {
+        let report = parser(text).validate(schema).unwrap();
+        report
+            .violations
+            .into_iter()
+            .map(|violation| (violation.row, violation.problem))
+            .collect()
+    }
+
+    #[test]
+    fn reads_the_same_schema_from_toml_and_json() {
+        let toml = Schema::from_toml(
+            "[[columns]]\n\
+             name = \"Age\"\n\
+             type = \"integer\"\n\
+             nullable = false\n\
+             min = 0\n\
+             max = 150\n\
+             \n\
+             [[columns]]\n\
+             name = \"City\"\n\
+             required = false\n\
+             allowed = [\"London\", \"Paris\"]\n",
+        )
+        .unwrap();
+        let json = Schema::from_json(
+            r#"{"columns": [
+                {"name": "Age", "type": "integer", "nullable": false, "min": 0, "max": 150},
+                {"name": "City", "required": false, "allowed": ["London", "Paris"]}
+            ]}"#,
+        )
+        .unwrap();
+        let built = Schema::new()
+            .column(
+                ColumnRule::new("Age")
+                    .column_type(ColumnType::Integer)
+                    .nullable(false)
+                    .min(0.0)
+                    .max(150.0),
+            )
+            .column(
+                ColumnRule::new("City")
+                    .required(false)
+                    .allowed(&["London", "Paris"]),
+            );
+        assert_eq!(toml, built);
+        assert_eq!(json, built);
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_bad_syntax() {
+        let err = Schema::from_toml("[[columns]]\nname = \"Age\"\nsize = 3\n").unwrap_err();
+        assert!(matches!(err, CsvError::InvalidSchema { .. }));
+        let err = Schema::from_json("{\"columns\": [").unwrap_err();
+        assert!(matches!(err, CsvError::InvalidSchema { .. }));
+    }
+
+    #[test]
+    fn passes_data_that_meets_the_schema() {
+        let schema = Schema::new()
+            .column(ColumnRule::new("Name").nullable(false))
+            .column(ColumnRule::new("2").pattern("[A-Z][a-z]+"));
+        let report = parser(PEOPLE).validate(&schema).unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.rows, 3);
+        assert_eq!(report.to_string(), "");
+    }
+
+    #[test]
+    fn reports_missing_columns_unless_optional() {
+        let schema = Schema::new()
+            .column(ColumnRule::new("Email"))
+            .column(ColumnRule::new("Phone").required(false))
+            .column(ColumnRule::new("7"));
+        assert_eq!(
+            problems(&schema, PEOPLE),
+            [
+                (None, Problem::MissingColumn),
+                (None, Problem::MissingColumn)
+            ]
+        );
+        let report = parser(PEOPLE).validate(&schema).unwrap();
+        assert_eq!(report.violations[0].to_string(), "column Email: missing");
+    }
+
+    #[test]
+    fn reports_each_kind_of_bad_cell() {
+        let text = "Age,Code,City\n\
+                    31,AB,London\n\
+                    ,AB,London\n\
+                    old,AB,London\n\
+                    -1,AB,London\n\
+                    40,abc,London\n\
+                    41,AB,Rome\n";
+        let schema = Schema::new()
+            .column(
+                ColumnRule::new("Age")
+                    .column_type(ColumnType::Integer)
+                    .nullable(false)
+                    .min(0.0),
+            )
+            .column(ColumnRule::new("Code").pattern("[A-Z]+"))
+            .column(ColumnRule::new("City").allowed(&["London", "Paris"]));
+        assert_eq!(
+            problems(&schema, text),
+            [
+                (Some(1), Problem::Null),
+                (Some(2), Problem::WrongType(ColumnType::Integer)),
+                (
+                    Some(3),
+                    Problem::OutOfRange {
+                        min: Some(0.0),
+                        max: None
+                    }
+                ),
+                (Some(4), Problem::NoMatch("[A-Z]+".to_string())),
+                (Some(5), Problem::NotAllowed),
+            ]
+        );
+    }
+
+    #[test]
+    fn tells_a_cell_that_is_not_a_number_from_one_out_of_range() {
+        let schema = Schema::new().column(ColumnRule::new("Age").min(0.0).max(150.0));
+        let report = parser("Age\nold\n151\n").validate(&schema).unwrap();
+        assert_eq!(
+            report.to_string(),
+            "row 0, column Age: \"old\" is not a number\n\
+             row 1, column Age: \"151\" is out of range, expected 0 to 150\n"
+        );
+    }
+
+    #[test]
+    fn reports_only_the_first_rule_a_cell_breaks() {
+        // "x" is the wrong type, fails the pattern and is not allowed.
+        let schema = Schema::new().column(
+            ColumnRule::new("Age")
+                .column_type(ColumnType::Integer)
+                .pattern("[0-9]+")
+                .max(10.0)
+                .allowed(&["1"]),
+        );
+        assert_eq!(
+            problems(&schema, "Age\nx\n20\n5\n"),
+            [
+                (Some(0), Problem::WrongType(ColumnType::Integer)),
+                (
+                    Some(1),
+                    Problem::OutOfRange {
+                        min: None,
+                        max: Some(10.0)
+                    }
+                ),
+                (Some(2), Problem::NotAllowed),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_repeats_in_a_unique_column_but_not_nulls() {
+        let schema = Schema::new()
+            .column(ColumnRule::new("Name").unique(true))
+            .column(ColumnRule::new("Age").unique(true));
+        assert_eq!(
+            problems(&schema, PEOPLE),
+            [(Some(2), Problem::Duplicate { first_row: 0 })]
+        );
+        let report = parser(PEOPLE).validate(&schema).unwrap();
+        assert_eq!(
+            report.to_string(),
+            "row 2, column Name: \"Ann\" already appears in row 0\n"
+        );
+    }
+
+    #[test]
+    fn matches_patterns_in_full() {
+        let schema = Schema::new().column(ColumnRule::new("City").pattern("[A-Z][a-z]+"));
+        assert!(problems(&schema, "City\nLondon\n").is_empty());
+        assert_eq!(
+            problems(&schema, "City\nNew York\n"),
+            [(Some(0), Problem::NoMatch("[A-Z][a-z]+".to_string()))]
+        );
+    }
+
+    #[test]
+    fn rejects_an_invalid_pattern() {
+        let schema = Schema::new().column(ColumnRule::new("City").pattern("("));
+        let err = parser(PEOPLE).validate(&schema).unwrap_err();
+        let CsvError::InvalidSchema { message } = err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert!(message.starts_with("column City: "), "{}", message);
+        assert!(!message.contains("^(?:"), "{}", message);
+    }
+}
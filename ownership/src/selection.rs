@@ -0,0 +1,119 @@
+use std::io::Write;
+
+use crate::dialect::Dialect;
+use crate::error::CsvError;
+use crate::table::{self, TableOptions};
+use crate::{writer, Row};
+
+/// A borrowed subset of a [`CSVParser`](crate::CSVParser)'s rows, as
+/// returned by [`CSVParser::filter`](crate::CSVParser::filter).
+///
+/// Rows keep their order and remember their index in the parser, so they can
+/// be edited there afterwards.
+#[derive(Clone, Debug)]
+pub struct Selection<'a> {
+    headers: Option<&'a Row>,
+    rows: Vec<(usize, &'a Row)>,
+    dialect: Dialect,
+}
+
+impl<'a> Selection<'a> {
+    pub(crate) fn new(
+        headers: Option<&'a Row>,
+        rows: Vec<(usize, &'a Row)>,
+        dialect: Dialect,
+    ) -> Self {
+        Selection {
+            headers,
+            rows,
+            dialect,
+        }
+    }
+
+    /// The parser's header row, if it has one.
+    pub fn headers(&self) -> Option<&'a Row> {
+        self.headers
+    }
+
+    /// The number of selected rows.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether no rows were selected.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The `index`th selected row.
+    pub fn get(&self, index: usize) -> Option<&'a Row> {
+        self.rows.get(index).map(|&(_, row)| row)
+    }
+
+    /// The selected rows, in order.
+    pub fn rows(&self) -> impl Iterator<Item = &'a Row> + '_ {
+        self.rows.iter().map(|&(_, row)| row)
+    }
+
+    /// Where each selected row is in the parser, in order.
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.rows.iter().map(|&(index, _)| index)
+    }
+
+    /// Narrows the selection to the rows for which `predicate` holds.
+    pub fn filter<F>(mut self, mut predicate: F) -> Self
+    where
+        F: FnMut(&Row) -> bool,
+    {
+        self.rows.retain(|&(_, row)| predicate(row));
+        self
+    }
+
+    /// Writes the headers, if any, and the selected rows to `sink` in the
+    /// parser's dialect.
+    pub fn write_to<W: Write>(&self, mut sink: W) -> Result<(), CsvError> {
+        for row in self.headers.into_iter().chain(self.rows()) {
+            writer::write_record(&mut sink, row, &self.dialect)?;
+        }
+        Ok(())
+    }
+
+    /// Renders the headers, if any, and the selected rows as an aligned text
+    /// table.
+    pub fn to_table(&self, options: &TableOptions) -> String {
+        let rows: Vec<Row> = self.rows().cloned().collect();
+        table::render_table(
+            self.headers.map(|headers| headers.as_slice()),
+            &rows,
+            options,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{parser, row};
+
+    const PEOPLE: &str = "Name,Age\nAnn,31\nBob,17\nCid,40\n";
+
+    #[test]
+    fn remembers_where_rows_came_from() {
+        let parser = parser(PEOPLE);
+        let adults = parser.filter_expr("Age >= 18").unwrap();
+        assert_eq!(adults.len(), 2);
+        assert_eq!(adults.indices().collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(adults.get(1), Some(&row(&["Cid", "40"])));
+        let named_c = adults.filter(|row| row[0].starts_with('C'));
+        assert_eq!(named_c.indices().collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn writes_the_headers_and_selected_rows() {
+        let parser = parser(PEOPLE);
+        let selection = parser.filter(|row| row[0] == "Bob");
+        let mut out = Vec::new();
+        selection.write_to(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Name,Age\nBob,17\n");
+        assert!(parser.filter(|_| false).is_empty());
+    }
+}
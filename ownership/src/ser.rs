@@ -0,0 +1,447 @@
+use serde::ser::{
+    Impossible, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple,
+    SerializeTupleStruct, Serializer,
+};
+
+use crate::error::FieldError;
+use crate::Row;
+
+/// Serializes one record into a row. Structs and maps also yield their
+/// field names, for use as a header row.
+pub(crate) fn to_row<T: Serialize>(record: &T) -> Result<(Option<Row>, Row), FieldError> {
+    let mut serializer = RecordSerializer {
+        names: None,
+        row: Row::new(),
+    };
+    record.serialize(&mut serializer)?;
+    Ok((serializer.names, serializer.row))
+}
+
+fn unsupported(what: &str) -> FieldError {
+    FieldError {
+        column: None,
+        message: format!("cannot write {} as a CSV field", what),
+    }
+}
+
+struct RecordSerializer {
+    names: Option<Row>,
+    row: Row,
+}
+
+impl RecordSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), FieldError> {
+        let column = self.row.len();
+        let cell = value
+            .serialize(CellSerializer)
+            .map_err(|err| err.at(column))?;
+        self.row.push(cell);
+        Ok(())
+    }
+
+    fn push_named<T: Serialize + ?Sized>(
+        &mut self,
+        name: String,
+        value: &T,
+    ) -> Result<(), FieldError> {
+        self.names.get_or_insert_with(Row::new).push(name);
+        self.push(value)
+    }
+}
+
+// A bare scalar is a record with a single field.
+macro_rules! serialize_scalar {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method(self, value: $ty) -> Result<(), FieldError> {
+                self.push(&value)
+            }
+        )*
+    };
+}
+
+impl Serializer for &mut RecordSerializer {
+    type Ok = ();
+    type Error = FieldError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Impossible<(), FieldError>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), FieldError>;
+
+    serialize_scalar! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), FieldError> {
+        let cell = CellSerializer.serialize_bytes(value)?;
+        self.row.push(cell);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), FieldError> {
+        self.push(&())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), FieldError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), FieldError> {
+        self.push(&())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), FieldError> {
+        self.push(&())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), FieldError> {
+        self.push(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), FieldError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), FieldError> {
+        Err(unsupported("an enum variant with data"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, FieldError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, FieldError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, FieldError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, FieldError> {
+        Err(unsupported("an enum variant with data"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self, FieldError> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, FieldError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, FieldError> {
+        Err(unsupported("an enum variant with data"))
+    }
+}
+
+impl SerializeSeq for &mut RecordSerializer {
+    type Ok = ();
+    type Error = FieldError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), FieldError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), FieldError> {
+        Ok(())
+    }
+}
+
+impl SerializeTuple for &mut RecordSerializer {
+    type Ok = ();
+    type Error = FieldError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), FieldError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), FieldError> {
+        Ok(())
+    }
+}
+
+impl SerializeTupleStruct for &mut RecordSerializer {
+    type Ok = ();
+    type Error = FieldError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), FieldError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), FieldError> {
+        Ok(())
+    }
+}
+
+impl SerializeMap for &mut RecordSerializer {
+    type Ok = ();
+    type Error = FieldError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), FieldError> {
+        let name = key.serialize(CellSerializer)?;
+        self.names.get_or_insert_with(Row::new).push(name);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), FieldError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), FieldError> {
+        Ok(())
+    }
+}
+
+impl SerializeStruct for &mut RecordSerializer {
+    type Ok = ();
+    type Error = FieldError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), FieldError> {
+        self.push_named(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<(), FieldError> {
+        Ok(())
+    }
+}
+
+/// Turns a single field value into the text of a cell. `None` and unit
+/// values become empty cells; nested containers are rejected.
+struct CellSerializer;
+
+macro_rules! serialize_display {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method(self, value: $ty) -> Result<String, FieldError> {
+                Ok(value.to_string())
+            }
+        )*
+    };
+}
+
+impl Serializer for CellSerializer {
+    type Ok = String;
+    type Error = FieldError;
+    type SerializeSeq = Impossible<String, FieldError>;
+    type SerializeTuple = Impossible<String, FieldError>;
+    type SerializeTupleStruct = Impossible<String, FieldError>;
+    type SerializeTupleVariant = Impossible<String, FieldError>;
+    type SerializeMap = Impossible<String, FieldError>;
+    type SerializeStruct = Impossible<String, FieldError>;
+    type SerializeStructVariant = Impossible<String, FieldError>;
+
+    serialize_display! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<String, FieldError> {
+        String::from_utf8(value.to_vec()).map_err(|_| unsupported("non-UTF-8 bytes"))
+    }
+
+    fn serialize_none(self) -> Result<String, FieldError> {
+        Ok(String::new())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, FieldError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, FieldError> {
+        Ok(String::new())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, FieldError> {
+        Ok(String::new())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<String, FieldError> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, FieldError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, FieldError> {
+        Err(unsupported("an enum variant with data"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, FieldError> {
+        Err(unsupported("a nested sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, FieldError> {
+        Err(unsupported("a nested tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, FieldError> {
+        Err(unsupported("a nested tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, FieldError> {
+        Err(unsupported("an enum variant with data"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, FieldError> {
+        Err(unsupported("a nested map"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, FieldError> {
+        Err(unsupported("a nested struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, FieldError> {
+        Err(unsupported("an enum variant with data"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use super::*;
+    use crate::testing::row;
+
+    #[derive(Serialize)]
+    struct Person {
+        #[serde(rename = "Full Name")]
+        name: &'static str,
+        age: Option<u32>,
+        member: bool,
+    }
+
+    #[test]
+    fn writes_struct_fields_with_their_names() {
+        let person = Person {
+            name: "Ann",
+            age: None,
+            member: true,
+        };
+        let (names, cells) = to_row(&person).unwrap();
+        assert_eq!(names, Some(row(&["Full Name", "age", "member"])));
+        assert_eq!(cells, row(&["Ann", "", "true"]));
+    }
+
+    #[test]
+    fn writes_maps_with_their_keys() {
+        let record = BTreeMap::from([("b", 2.5), ("a", 1.0)]);
+        let (names, cells) = to_row(&record).unwrap();
+        assert_eq!(names, Some(row(&["a", "b"])));
+        assert_eq!(cells, row(&["1", "2.5"]));
+    }
+
+    #[test]
+    fn writes_tuples_and_scalars_without_names() {
+        assert_eq!(
+            to_row(&("x", 1, 'c')).unwrap(),
+            (None, row(&["x", "1", "c"]))
+        );
+        assert_eq!(to_row(&7).unwrap(), (None, row(&["7"])));
+    }
+
+    #[test]
+    fn rejects_nested_containers() {
+        let err = to_row(&("x", vec![1, 2])).unwrap_err();
+        assert_eq!(err.column, Some(1));
+    }
+}
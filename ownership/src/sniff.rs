@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+
+use crate::dialect::{Dialect, Escape, LineTerminator};
+use crate::error::CsvError;
+use crate::reader::CsvReader;
+use crate::writer::{self, QuoteStyle};
+use crate::Row;
+
+/// How much of the input `sniff_path` looks at by default.
+pub const DEFAULT_SAMPLE_SIZE: usize = 64 * 1024;
+
+const DELIMITERS: [char; 5] = [',', ';', '\t', '|', ':'];
+const QUOTES: [char; 2] = ['"', '\''];
+// Rows past the first that are compared against it when looking for a header.
+const HEADER_SAMPLE_ROWS: usize = 20;
+// Samples with fewer records than this get a proportionally lower confidence.
+const CONFIDENT_RECORDS: usize = 10;
+
+/// The result of sniffing a sample of unknown input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sniffed {
+    /// The inferred dialect, including line terminator and quoting style.
+    pub dialect: Dialect,
+    /// Whether the first record looks like a header row.
+    pub has_header: bool,
+    /// From 0.0 to 1.0: the share of sampled records that agree with the
+    /// inferred field count, reduced for very small or single-column samples.
+    pub confidence: f64,
+}
+
+/// Infers the dialect of the file at `path` from its first
+/// [`DEFAULT_SAMPLE_SIZE`] bytes.
+pub fn sniff_path(path: &str) -> Result<Sniffed, CsvError> {
+    sniff(File::open(path)?, DEFAULT_SAMPLE_SIZE)
+}
+
+/// Infers delimiter, quote and escape characters, quoting style, line ending
+/// and the presence of a header row from the first `sample_size` bytes of
+/// `input`.
+pub fn sniff<R: Read>(input: R, sample_size: usize) -> Result<Sniffed, CsvError> {
+    let mut bytes = Vec::new();
+    input.take(sample_size as u64).read_to_end(&mut bytes)?;
+    // A full sample most likely ends mid-record; drop the partial last line.
+    if bytes.len() == sample_size {
+        if let Some(end) = bytes.iter().rposition(|&b| b == b'\n') {
+            bytes.truncate(end + 1);
+        }
+    }
+    let sample = String::from_utf8_lossy(&bytes);
+    if sample.trim().is_empty() {
+        return Err(CsvError::EmptyInput);
+    }
+
+    let mut best: Option<Candidate> = None;
+    for quote in QUOTES {
+        for delimiter in DELIMITERS {
+            let dialect = Dialect::new().delimiter(delimiter).quote(quote);
+            let rows = parse_sample(&sample, &dialect);
+            let (consistency, width) = consistency(&rows);
+            let candidate = Candidate {
+                dialect,
+                rows,
+                consistency,
+                width,
+            };
+            // Earlier candidates win ties, so `,` and `"` are preferred.
+            if best.as_ref().is_none_or(|b| candidate.score() > b.score()) {
+                best = Some(candidate);
+            }
+        }
+    }
+    let Candidate {
+        mut dialect,
+        rows,
+        consistency,
+        width,
+    } = best.expect("at least one candidate dialect");
+
+    dialect.escape = detect_escape(&sample, dialect.quote);
+    dialect.terminator = detect_terminator(&sample);
+    dialect.quote_style = detect_quote_style(&sample, &dialect);
+
+    let mut confidence = consistency * rows.len().min(CONFIDENT_RECORDS) as f64;
+    confidence /= CONFIDENT_RECORDS as f64;
+    if width <= 1 {
+        confidence *= 0.5;
+    }
+
+    Ok(Sniffed {
+        dialect,
+        has_header: detect_header(&rows),
+        confidence,
+    })
+}
+
+struct Candidate {
+    dialect: Dialect,
+    rows: Vec<Row>,
+    consistency: f64,
+    width: usize,
+}
+
+impl Candidate {
+    /// Consistent splitting into several fields beats everything else; more
+    /// fields break ties between equally consistent delimiters.
+    fn score(&self) -> (f64, usize) {
+        let consistency = if self.width > 1 {
+            self.consistency
+        } else {
+            0.0
+        };
+        (consistency, self.width)
+    }
+}
+
+fn parse_sample(sample: &str, dialect: &Dialect) -> Vec<Row> {
+    // A wrong quote guess, or a sample cut inside a quoted field, ends in an
+    // unterminated quote; whatever was read before that still counts.
+    CsvReader::new(sample.as_bytes())
+        .with_dialect(*dialect)
+        .map_while(Result::ok)
+        .collect()
+}
+
+/// Returns the most common field count and the share of rows that have it.
+fn consistency(rows: &[Row]) -> (f64, usize) {
+    if rows.is_empty() {
+        return (0.0, 0);
+    }
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for row in rows {
+        *counts.entry(row.len()).or_default() += 1;
+    }
+    let (width, matching) = counts
+        .into_iter()
+        .max_by_key(|&(width, matching)| (matching, width))
+        .unwrap_or((0, 0));
+    (matching as f64 / rows.len() as f64, width)
+}
+
+fn detect_escape(sample: &str, quote: char) -> Escape {
+    let backslashed = sample.matches(&format!("\\{}", quote)).count();
+    let doubled = sample.matches(&format!("{}{}", quote, quote)).count();
+    if backslashed > doubled {
+        Escape::Char('\\')
+    } else {
+        Escape::Doubled
+    }
+}
+
+fn detect_terminator(sample: &str) -> LineTerminator {
+    let crlf = sample.matches("\r\n").count();
+    let lf = sample.matches('\n').count() - crlf;
+    if crlf > lf {
+        LineTerminator::CrLf
+    } else {
+        LineTerminator::Lf
+    }
+}
+
+/// Picks the quoting style that reproduces the most sample lines verbatim.
+fn detect_quote_style(sample: &str, dialect: &Dialect) -> QuoteStyle {
+    let styles = [
+        QuoteStyle::Necessary,
+        QuoteStyle::NonNumeric,
+        QuoteStyle::Always,
+    ];
+    let mut matches = [0; 3];
+    for line in sample.lines().filter(|line| !line.is_empty()) {
+        let Some(Ok(row)) = CsvReader::new(line.as_bytes())
+            .with_dialect(*dialect)
+            .next()
+        else {
+            continue;
+        };
+        for (style, count) in styles.iter().zip(matches.iter_mut()) {
+            if writer::format_record(&row, &dialect.quote_style(*style)) == line {
+                *count += 1;
+            }
+        }
+    }
+    // `max_by_key` keeps the last maximum, so iterate in reverse to let ties
+    // go to the earliest, least aggressive style.
+    let best = (0..styles.len()).rev().max_by_key(|&i| matches[i]);
+    styles[best.unwrap_or(0)]
+}
+
+#[derive(PartialEq)]
+enum CellKind {
+    Numeric,
+    Length(usize),
+}
+
+/// The first row is taken to be a header when its cells do not fit the
+/// shape of the column below them: text above numbers, or a different length
+/// above values that all have the same length. Text above numbers is the
+/// stronger sign, so it outweighs a name that happens to be as long as the
+/// values below it.
+fn detect_header(rows: &[Row]) -> bool {
+    let Some((header, body)) = rows.split_first() else {
+        return false;
+    };
+    let body = &body[..body.len().min(HEADER_SAMPLE_ROWS)];
+    if body.is_empty() {
+        return false;
+    }
+
+    let mut votes = 0i32;
+    for (col, name) in header.iter().enumerate() {
+        let mut kind = None;
+        for row in body {
+            let Some(cell) = row.get(col) else {
+                kind = None;
+                break;
+            };
+            let cell_kind = cell_kind(cell);
+            match &kind {
+                None => kind = Some(cell_kind),
+                Some(k) if *k == cell_kind => {}
+                Some(_) => {
+                    kind = None;
+                    break;
+                }
+            }
+        }
+        match kind {
+            Some(kind) if kind == cell_kind(name) => votes -= 1,
+            Some(CellKind::Numeric) => votes += 2,
+            Some(_) => votes += 1,
+            None => {}
+        }
+    }
+    votes > 0
+}
+
+fn cell_kind(cell: &str) -> CellKind {
+    if !cell.is_empty() && cell.trim().parse::<f64>().is_ok() {
+        CellKind::Numeric
+    } else {
+        CellKind::Length(cell.chars().count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sniff_str(sample: &str) -> Sniffed {
+        sniff(sample.as_bytes(), DEFAULT_SAMPLE_SIZE).unwrap()
+    }
+
+    #[test]
+    fn sniffs_a_semicolon_file() {
+        let sniffed = sniff_str("name;age;city\nAnn;31;Paris\nBob;28;\"Rome; Italy\"\n");
+        assert_eq!(sniffed.dialect.delimiter, ';');
+        assert_eq!(sniffed.dialect.quote, '"');
+        assert!(sniffed.has_header);
+    }
+
+    #[test]
+    fn sniffs_a_tsv_file() {
+        let sniffed = sniff_str("id\tscore\n1\t9.5\n2\t7.25\n3\t8\n");
+        assert_eq!(sniffed.dialect.delimiter, '\t');
+        assert!(sniffed.has_header);
+    }
+
+    #[test]
+    fn sniffs_backslash_escapes() {
+        let sniffed =
+            sniff_str("\"a\",\"He said \\\"hi\\\"\"\n\"b\",\"\\\"quoted\\\"\"\n\"c\",\"plain\"\n");
+        assert_eq!(sniffed.dialect.delimiter, ',');
+        assert_eq!(sniffed.dialect.escape, Escape::Char('\\'));
+    }
+
+    #[test]
+    fn sniffs_crlf_line_endings() {
+        let sniffed = sniff_str("a,b\r\n1,2\r\n3,4\r\n");
+        assert_eq!(sniffed.dialect.terminator, LineTerminator::CrLf);
+        assert_eq!(sniffed.dialect.escape, Escape::Doubled);
+    }
+
+    #[test]
+    fn finds_a_header_as_long_as_the_numbers_below_it() {
+        // `Age` is as long as every value below it, so only the text above
+        // numbers says it is a header.
+        let sniffed = sniff_str("Name,Age\nJohn,32\nJane,28\n");
+        assert!(sniffed.has_header);
+        let sniffed = sniff_str("Name,Age\nJohn,100\nJane,250\n");
+        assert!(sniffed.has_header);
+    }
+
+    #[test]
+    fn finds_no_header_above_rows_like_it() {
+        assert!(!sniff_str("1,2\n3,4\n5,6\n").has_header);
+        assert!(!sniff_str("Ann,Paris\nBob,Rome\nCid,Oslo\n").has_header);
+    }
+
+    #[test]
+    fn is_less_confident_about_few_records() {
+        let few = sniff_str("a,b\n1,2\n");
+        let many = sniff_str(&"a,b\n".repeat(20));
+        assert!(few.confidence < many.confidence);
+        assert_eq!(many.confidence, 1.0);
+    }
+
+    #[test]
+    fn fails_on_empty_input() {
+        let sniffed = sniff("  \n".as_bytes(), DEFAULT_SAMPLE_SIZE);
+        assert!(matches!(sniffed, Err(CsvError::EmptyInput)));
+    }
+}
@@ -0,0 +1,364 @@
+use std::cmp::Ordering;
+
+use crate::date::Date;
+use crate::error::CsvError;
+use crate::parser::resolve_column;
+use crate::value::ColumnType;
+use crate::Row;
+
+/// Which way a column is sorted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Order {
+    /// Smallest first.
+    #[default]
+    Asc,
+    /// Largest first.
+    Desc,
+}
+
+/// How the cells of a sort column are compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Collation {
+    /// Picks one of the others from the column's type where the parser knows
+    /// it, and otherwise from its contents: numeric if every non-empty cell
+    /// is a number, date if every one is a [`Date`], natural if any contains
+    /// a digit, and lexical otherwise.
+    #[default]
+    Auto,
+    /// As numbers.
+    Numeric,
+    /// As text, character by character.
+    Lexical,
+    /// As text, but with runs of digits compared as numbers, so `file2`
+    /// comes before `file10`.
+    Natural,
+    /// As dates.
+    Date,
+}
+
+/// One column to sort by, for [`CSVParser::sort_by_keys`](crate::CSVParser::sort_by_keys).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortKey {
+    /// The column's header, or its index.
+    pub column: String,
+    /// Which way it is sorted.
+    pub order: Order,
+    /// How its cells are compared.
+    pub collation: Collation,
+}
+
+impl SortKey {
+    /// Sorts ascending by `column`, a header or an index, comparing cells as
+    /// suits the column's contents.
+    pub fn new(column: &str) -> Self {
+        SortKey {
+            column: column.to_string(),
+            order: Order::Asc,
+            collation: Collation::Auto,
+        }
+    }
+
+    /// Sets which way the column is sorted.
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Sets how the column's cells are compared.
+    pub fn collation(mut self, collation: Collation) -> Self {
+        self.collation = collation;
+        self
+    }
+}
+
+/// A cell as it is compared. Cells that do not fit the column's collation
+/// and empty cells come after the rest, whichever way the column is sorted.
+enum SortValue<'a> {
+    Number(f64),
+    Date(Date),
+    Text(&'a str),
+    Natural(&'a str),
+    Invalid(&'a str),
+    Empty,
+}
+
+/// The order `data` would be in after a stable sort by `keys`: the index of
+/// the row that goes first, then the second, and so on. `types` are the
+/// columns' types, if known.
+pub(crate) fn sorted_order(
+    headers: Option<&Row>,
+    data: &[Row],
+    types: Option<&[ColumnType]>,
+    keys: &[SortKey],
+) -> Result<Vec<usize>, CsvError> {
+    let width = headers
+        .into_iter()
+        .chain(data)
+        .map(|row| row.len())
+        .max()
+        .unwrap_or(0);
+    let mut columns = Vec::with_capacity(keys.len());
+    for key in keys {
+        let col = resolve_column(headers, &key.column, width)?;
+        let collation = match key.collation {
+            Collation::Auto => match types.and_then(|types| types.get(col)) {
+                Some(&column_type) => collation_for(column_type),
+                None => infer(data, col),
+            },
+            collation => collation,
+        };
+        columns.push((col, collation, key.order));
+    }
+
+    let values: Vec<Vec<SortValue>> = data
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|&(col, collation, _)| {
+                    sort_value(row.get(col).map_or("", String::as_str), collation)
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.sort_by(|&a, &b| {
+        values[a]
+            .iter()
+            .zip(&values[b])
+            .zip(&columns)
+            .map(|((a, b), &(_, _, order))| compare(a, b, order))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+    Ok(order)
+}
+
+/// The collation [`Collation::Auto`] picks for column `col` of `data`.
+pub(crate) fn infer(data: &[Row], col: usize) -> Collation {
+    let cells = || {
+        data.iter()
+            .filter_map(|row| row.get(col))
+            .map(|cell| cell.trim())
+            .filter(|cell| !cell.is_empty())
+    };
+    if cells().next().is_none() {
+        Collation::Lexical
+    } else if cells().all(|cell| cell.parse::<f64>().is_ok()) {
+        Collation::Numeric
+    } else if cells().all(|cell| Date::parse(cell).is_some()) {
+        Collation::Date
+    } else if cells().any(|cell| cell.bytes().any(|b| b.is_ascii_digit())) {
+        Collation::Natural
+    } else {
+        Collation::Lexical
+    }
+}
+
+/// How cells of a column declared as `column_type` are compared.
+fn collation_for(column_type: ColumnType) -> Collation {
+    match column_type {
+        ColumnType::Integer | ColumnType::Float => Collation::Numeric,
+        ColumnType::Date | ColumnType::DateTime => Collation::Date,
+        ColumnType::Boolean | ColumnType::Text => Collation::Lexical,
+    }
+}
+
+/// Compares two cells of a column sorted ascending by `collation`.
+pub(crate) fn compare_cells(a: &str, b: &str, collation: Collation) -> Ordering {
+    compare(
+        &sort_value(a, collation),
+        &sort_value(b, collation),
+        Order::Asc,
+    )
+}
+
+fn sort_value(cell: &str, collation: Collation) -> SortValue<'_> {
+    if cell.trim().is_empty() {
+        return SortValue::Empty;
+    }
+    match collation {
+        Collation::Numeric => match cell.trim().parse::<f64>() {
+            Ok(number) if !number.is_nan() => SortValue::Number(number),
+            _ => SortValue::Invalid(cell),
+        },
+        Collation::Date => match Date::parse(cell) {
+            Some(date) => SortValue::Date(date),
+            None => SortValue::Invalid(cell),
+        },
+        Collation::Natural => SortValue::Natural(cell),
+        Collation::Lexical | Collation::Auto => SortValue::Text(cell),
+    }
+}
+
+fn compare(a: &SortValue, b: &SortValue, order: Order) -> Ordering {
+    let ordering = match (a, b) {
+        (SortValue::Number(a), SortValue::Number(b)) => a.total_cmp(b),
+        (SortValue::Date(a), SortValue::Date(b)) => a.cmp(b),
+        (SortValue::Text(a), SortValue::Text(b)) => a.cmp(b),
+        (SortValue::Natural(a), SortValue::Natural(b)) => natural_cmp(a, b),
+        // Left over cells keep to the end in plain text order.
+        (SortValue::Invalid(a), SortValue::Invalid(b)) => return a.cmp(b),
+        _ => return rank(a).cmp(&rank(b)),
+    };
+    match order {
+        Order::Asc => ordering,
+        Order::Desc => ordering.reverse(),
+    }
+}
+
+fn rank(value: &SortValue) -> u8 {
+    match value {
+        SortValue::Invalid(_) => 1,
+        SortValue::Empty => 2,
+        _ => 0,
+    }
+}
+
+/// Compares text with runs of ASCII digits taken as whole numbers.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        match (a.chars().next(), b.chars().next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x_digits, x_rest) = split_digits(a);
+                let (y_digits, y_rest) = split_digits(b);
+                let x_number = x_digits.trim_start_matches('0');
+                let y_number = y_digits.trim_start_matches('0');
+                let ordering = x_number
+                    .len()
+                    .cmp(&y_number.len())
+                    .then_with(|| x_number.cmp(y_number))
+                    .then_with(|| x_digits.len().cmp(&y_digits.len()));
+                if ordering.is_ne() {
+                    return ordering;
+                }
+                (a, b) = (x_rest, y_rest);
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+            }
+        }
+    }
+}
+
+fn split_digits(text: &str) -> (&str, &str) {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text.split_at(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{row, rows};
+
+    /// The first cell of each row of `data`, in order after sorting by `key`.
+    fn sorted(data: &[Row], key: SortKey) -> Vec<String> {
+        sorted_order(None, data, None, &[key])
+            .unwrap()
+            .into_iter()
+            .map(|index| data[index][0].clone())
+            .collect()
+    }
+
+    fn column(cells: &[&str]) -> Vec<Row> {
+        cells.iter().map(|cell| row(&[cell])).collect()
+    }
+
+    #[test]
+    fn compares_digit_runs_as_numbers() {
+        assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("a10b2", "a10b1"), Ordering::Greater);
+        assert_eq!(natural_cmp("x007", "x7"), Ordering::Greater);
+        assert_eq!(natural_cmp("x7", "x7"), Ordering::Equal);
+        assert_eq!(natural_cmp("x", "x1"), Ordering::Less);
+    }
+
+    #[test]
+    fn picks_a_collation_from_the_contents() {
+        let data = column(&["10", "9", "", "100"]);
+        assert_eq!(sorted(&data, SortKey::new("0")), ["9", "10", "100", ""]);
+        let data = column(&["file10", "file9", "file1"]);
+        assert_eq!(
+            sorted(&data, SortKey::new("0")),
+            ["file1", "file9", "file10"]
+        );
+        let data = column(&["b", "B", "a"]);
+        assert_eq!(sorted(&data, SortKey::new("0")), ["B", "a", "b"]);
+    }
+
+    #[test]
+    fn sorts_dates_chronologically() {
+        let data = column(&["2024/03/01", "2023-12-31", "2024-01-15T08:00"]);
+        let key = SortKey::new("0");
+        assert_eq!(
+            sorted(&data, key.clone()),
+            ["2023-12-31", "2024-01-15T08:00", "2024/03/01"]
+        );
+        let key = key.collation(Collation::Date).order(Order::Desc);
+        assert_eq!(
+            sorted(&data, key),
+            ["2024/03/01", "2024-01-15T08:00", "2023-12-31"]
+        );
+    }
+
+    #[test]
+    fn puts_invalid_then_blank_cells_last_either_way() {
+        let data = column(&["", "3", "n/a", "1", " ", "abc", "2"]);
+        let key = SortKey::new("0").collation(Collation::Numeric);
+        assert_eq!(
+            sorted(&data, key.clone()),
+            ["1", "2", "3", "abc", "n/a", "", " "]
+        );
+        assert_eq!(
+            sorted(&data, key.order(Order::Desc)),
+            ["3", "2", "1", "abc", "n/a", "", " "]
+        );
+        let data = column(&["2024-01-01", "soon", "", "2023-01-01"]);
+        let key = SortKey::new("0")
+            .collation(Collation::Date)
+            .order(Order::Desc);
+        assert_eq!(sorted(&data, key), ["2024-01-01", "2023-01-01", "soon", ""]);
+    }
+
+    #[test]
+    fn breaks_ties_with_later_keys_and_keeps_the_rest_stable() {
+        let headers = row(&["Name", "Age", "City"]);
+        let data = rows(&[
+            &["Ann", "30", "Rome"],
+            &["Bob", "25", "Oslo"],
+            &["Cid", "30", "Oslo"],
+            &["Dan", "25", "Oslo"],
+        ]);
+        let keys = [SortKey::new("Age").order(Order::Desc), SortKey::new("City")];
+        let order = sorted_order(Some(&headers), &data, None, &keys).unwrap();
+        assert_eq!(order, [2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn names_an_unknown_column() {
+        let headers = row(&["Name"]);
+        let err = sorted_order(Some(&headers), &[], None, &[SortKey::new("Age")]).unwrap_err();
+        assert!(matches!(err, CsvError::UnknownColumn(name) if name == "Age"));
+    }
+
+    #[test]
+    fn compares_cells_as_their_declared_type() {
+        let data = column(&["10", "9", "100"]);
+        let types = [ColumnType::Text];
+        let order = sorted_order(None, &data, Some(&types), &[SortKey::new("0")]).unwrap();
+        assert_eq!(order, [0, 2, 1]);
+        let types = [ColumnType::Integer];
+        let order = sorted_order(None, &data, Some(&types), &[SortKey::new("0")]).unwrap();
+        assert_eq!(order, [1, 0, 2]);
+    }
+}
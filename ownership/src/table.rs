@@ -0,0 +1,287 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::Row;
+
+const ELLIPSIS: char = '…';
+
+/// How [`render_table`] lays out rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableOptions {
+    /// Cells wider than this many terminal columns are cut short with `…`.
+    pub max_cell_width: Option<usize>,
+    /// Show at most this many rows.
+    pub max_rows: Option<usize>,
+    /// Skip this many rows before the first one shown.
+    pub offset: usize,
+    /// Draw box borders; otherwise columns are only separated by spaces.
+    pub borders: bool,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        TableOptions {
+            max_cell_width: Some(40),
+            max_rows: None,
+            offset: 0,
+            borders: true,
+        }
+    }
+}
+
+impl TableOptions {
+    /// Bordered, with cells cut at 40 columns and every row shown.
+    pub fn new() -> Self {
+        TableOptions::default()
+    }
+
+    /// Sets the widest a cell may be, or `None` to never truncate.
+    pub fn max_cell_width(mut self, width: Option<usize>) -> Self {
+        self.max_cell_width = width;
+        self
+    }
+
+    /// Sets the most rows shown, or `None` for all of them.
+    pub fn max_rows(mut self, rows: Option<usize>) -> Self {
+        self.max_rows = rows;
+        self
+    }
+
+    /// Shows page `page`, counted from 1, of `rows_per_page` rows each.
+    pub fn page(mut self, page: usize, rows_per_page: usize) -> Self {
+        self.offset = page.saturating_sub(1) * rows_per_page;
+        self.max_rows = Some(rows_per_page);
+        self
+    }
+
+    /// Sets whether box borders are drawn.
+    pub fn borders(mut self, borders: bool) -> Self {
+        self.borders = borders;
+        self
+    }
+}
+
+/// Renders `rows` under `headers` as an aligned text table.
+///
+/// Widths are measured in terminal columns, so wide and combining characters
+/// line up. Columns whose shown cells are all numbers are right-aligned.
+/// When rows are left out, a footer says which ones are shown.
+pub fn render_table(headers: Option<&[String]>, rows: &[Row], options: &TableOptions) -> String {
+    let shown: Vec<&Row> = rows
+        .iter()
+        .skip(options.offset)
+        .take(options.max_rows.unwrap_or(usize::MAX))
+        .collect();
+    let columns = shown
+        .iter()
+        .map(|row| row.len())
+        .chain(headers.map(|headers| headers.len()))
+        .max()
+        .unwrap_or(0);
+
+    let fit = |cells: &[String]| -> Vec<String> {
+        (0..columns)
+            .map(|col| {
+                let cell = cells.get(col).map(String::as_str).unwrap_or("");
+                display_cell(cell, options.max_cell_width)
+            })
+            .collect()
+    };
+    let header_cells = headers.map(fit);
+    let body: Vec<Vec<String>> = shown.iter().map(|row| fit(row)).collect();
+
+    let mut widths = vec![0; columns];
+    for cells in header_cells.iter().chain(&body) {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.width());
+        }
+    }
+    let numeric: Vec<bool> = (0..columns)
+        .map(|col| {
+            let mut cells = shown
+                .iter()
+                .filter_map(|row| row.get(col))
+                .filter(|c| !c.is_empty());
+            let mut any = false;
+            let all = cells.all(|cell| {
+                any = true;
+                cell.trim().parse::<f64>().is_ok()
+            });
+            any && all
+        })
+        .collect();
+
+    let mut out = String::new();
+    if options.borders {
+        rule(&mut out, &widths, '┌', '┬', '┐');
+    }
+    if let Some(header_cells) = &header_cells {
+        line(&mut out, header_cells, &widths, &numeric, options.borders);
+        if options.borders {
+            rule(&mut out, &widths, '├', '┼', '┤');
+        } else {
+            let underline: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+            line(&mut out, &underline, &widths, &numeric, false);
+        }
+    }
+    for cells in &body {
+        line(&mut out, cells, &widths, &numeric, options.borders);
+    }
+    if options.borders {
+        rule(&mut out, &widths, '└', '┴', '┘');
+    }
+
+    if shown.len() < rows.len() {
+        if shown.is_empty() {
+            out.push_str(&format!("no rows shown, {} in total\n", rows.len()));
+        } else {
+            let first = options.offset + 1;
+            let last = options.offset + shown.len();
+            out.push_str(&format!("rows {}-{} of {}\n", first, last, rows.len()));
+        }
+    }
+    out
+}
+
+/// Formats `cell` for a single line of terminal output: line breaks show as
+/// `↵`, tabs as spaces, and anything wider than `max_width` columns is cut
+/// short with `…`.
+pub fn display_cell(cell: &str, max_width: Option<usize>) -> String {
+    truncate(&printable(cell), max_width)
+}
+
+fn rule(out: &mut String, widths: &[usize], left: char, middle: char, right: char) {
+    out.push(left);
+    for (i, &width) in widths.iter().enumerate() {
+        if i > 0 {
+            out.push(middle);
+        }
+        out.extend(std::iter::repeat_n('─', width + 2));
+    }
+    out.push(right);
+    out.push('\n');
+}
+
+fn line(out: &mut String, cells: &[String], widths: &[usize], right_align: &[bool], borders: bool) {
+    let mut text = String::new();
+    if borders {
+        text.push('│');
+    }
+    for (i, (cell, &width)) in cells.iter().zip(widths).enumerate() {
+        let padding = " ".repeat(width - cell.width());
+        if borders {
+            text.push(' ');
+        } else if i > 0 {
+            text.push_str("  ");
+        }
+        if right_align[i] {
+            text.push_str(&padding);
+            text.push_str(cell);
+        } else {
+            text.push_str(cell);
+            text.push_str(&padding);
+        }
+        if borders {
+            text.push_str(" │");
+        }
+    }
+    out.push_str(text.trim_end());
+    out.push('\n');
+}
+
+/// Keeps a cell on one line: line breaks show as `↵` and tabs as spaces.
+fn printable(cell: &str) -> String {
+    cell.replace("\r\n", "↵")
+        .chars()
+        .map(|c| match c {
+            '\n' | '\r' => '↵',
+            '\t' => ' ',
+            c => c,
+        })
+        .collect()
+}
+
+fn truncate(cell: &str, max_width: Option<usize>) -> String {
+    let Some(max_width) = max_width.map(|width| width.max(1)) else {
+        return cell.to_string();
+    };
+    if cell.width() <= max_width {
+        return cell.to_string();
+    }
+    let mut truncated = String::new();
+    let mut width = 0;
+    for c in cell.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if width + char_width + 1 > max_width {
+            break;
+        }
+        truncated.push(c);
+        width += char_width;
+    }
+    truncated.push(ELLIPSIS);
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{row, rows};
+
+    #[test]
+    fn draws_borders_and_right_aligns_numbers() {
+        let headers = row(&["Name", "Age"]);
+        let rows = rows(&[&["Ann", "7"], &["Bob", "31"]]);
+        let table = render_table(Some(&headers), &rows, &TableOptions::new());
+        assert_eq!(
+            table,
+            "┌──────┬─────┐\n\
+             │ Name │ Age │\n\
+             ├──────┼─────┤\n\
+             │ Ann  │   7 │\n\
+             │ Bob  │  31 │\n\
+             └──────┴─────┘\n"
+        );
+    }
+
+    #[test]
+    fn separates_plain_columns_with_spaces() {
+        let headers = row(&["Name", "Age"]);
+        let rows = rows(&[&["Ann", "31"]]);
+        let options = TableOptions::new().borders(false);
+        let table = render_table(Some(&headers), &rows, &options);
+        assert_eq!(table, "Name  Age\n----  ---\nAnn    31\n");
+    }
+
+    #[test]
+    fn pads_short_rows_to_the_widest() {
+        let rows = rows(&[&["a", "b"], &["c"]]);
+        let options = TableOptions::new().borders(false);
+        assert_eq!(render_table(None, &rows, &options), "a  b\nc\n");
+    }
+
+    #[test]
+    fn measures_wide_characters_in_terminal_columns() {
+        let rows = rows(&[&["日本", "x"], &["ab", "y"]]);
+        let options = TableOptions::new().borders(false);
+        assert_eq!(render_table(None, &rows, &options), "日本  x\nab    y\n");
+    }
+
+    #[test]
+    fn cuts_wide_cells_and_keeps_them_on_one_line() {
+        let rows = rows(&[&["abcdefgh"], &["two\nlines"]]);
+        let options = TableOptions::new().max_cell_width(Some(5)).borders(false);
+        assert_eq!(render_table(None, &rows, &options), "abcd…\ntwo↵…\n");
+    }
+
+    #[test]
+    fn shows_one_page_with_a_footer() {
+        let rows = rows(&[&["1"], &["2"], &["3"], &["4"], &["5"]]);
+        let options = TableOptions::new().page(2, 2).borders(false);
+        assert_eq!(render_table(None, &rows, &options), "3\n4\nrows 3-4 of 5\n");
+
+        let options = TableOptions::new().page(4, 2).borders(false);
+        assert_eq!(
+            render_table(None, &rows, &options),
+            "no rows shown, 5 in total\n"
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{CSVParser, Row};
+
+/// A row holding `cells`.
+pub fn row(cells: &[&str]) -> Row {
+    cells.iter().map(|cell| cell.to_string()).collect()
+}
+
+/// Rows holding `cells`, one per slice.
+pub fn rows(cells: &[&[&str]]) -> Vec<Row> {
+    cells.iter().map(|cells| row(cells)).collect()
+}
+
+/// A parser holding `text`, its first line read as the header row.
+pub fn parser(text: &str) -> CSVParser {
+    let mut parser = CSVParser::new().has_headers(true);
+    parser.parse_from(text.as_bytes()).unwrap();
+    parser
+}
+
+/// The header row, if any, and every data row of `parser`.
+pub fn contents(parser: &CSVParser) -> (Option<Row>, Vec<Row>) {
+    let data = (0..parser.len())
+        .filter_map(|row| parser.get_row(row).cloned())
+        .collect();
+    (parser.headers().cloned(), data)
+}
@@ -0,0 +1,262 @@
+use std::fmt;
+
+use crate::error::CsvError;
+use crate::parser::CSVParser;
+use crate::sort::SortKey;
+use crate::Row;
+
+/// A batch of edits staged against a [`CSVParser`] and applied all at once.
+///
+/// Nothing changes until [`Transaction::commit`]. Dropping a transaction
+/// without committing discards the staged edits.
+pub struct Transaction<'a> {
+    parser: &'a mut CSVParser,
+    staged: Vec<Staged<'a>>,
+}
+
+enum Staged<'a> {
+    UpdateCell(usize, usize, String),
+    UpdateByName(usize, String, String),
+    PushRow(Row),
+    InsertRow(usize, Row),
+    RemoveRow(usize),
+    InsertColumn(usize, String, Box<dyn FnMut(&Row) -> String + 'a>),
+    RemoveColumn(usize),
+    SwapColumns(usize, usize),
+    Sort(Vec<SortKey>),
+    RenameColumn(String, String),
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn new(parser: &'a mut CSVParser) -> Self {
+        Transaction {
+            parser,
+            staged: Vec::new(),
+        }
+    }
+
+    /// Stages [`CSVParser::update_cell`].
+    pub fn update_cell(&mut self, row_index: usize, col_index: usize, value: &str) -> &mut Self {
+        self.stage(Staged::UpdateCell(row_index, col_index, value.to_string()))
+    }
+
+    /// Stages [`CSVParser::update_by_name`].
+    pub fn update_by_name(&mut self, row_index: usize, name: &str, value: &str) -> &mut Self {
+        self.stage(Staged::UpdateByName(
+            row_index,
+            name.to_string(),
+            value.to_string(),
+        ))
+    }
+
+    /// Stages [`CSVParser::push_row`].
+    pub fn push_row(&mut self, row: Row) -> &mut Self {
+        self.stage(Staged::PushRow(row))
+    }
+
+    /// Stages [`CSVParser::insert_row`].
+    pub fn insert_row(&mut self, row_index: usize, row: Row) -> &mut Self {
+        self.stage(Staged::InsertRow(row_index, row))
+    }
+
+    /// Stages [`CSVParser::remove_row`].
+    pub fn remove_row(&mut self, row_index: usize) -> &mut Self {
+        self.stage(Staged::RemoveRow(row_index))
+    }
+
+    /// Stages [`CSVParser::insert_column`]. `value` sees each row as it is
+    /// when the edit is applied.
+    pub fn insert_column<F>(&mut self, col_index: usize, name: &str, value: F) -> &mut Self
+    where
+        F: FnMut(&Row) -> String + 'a,
+    {
+        self.stage(Staged::InsertColumn(
+            col_index,
+            name.to_string(),
+            Box::new(value),
+        ))
+    }
+
+    /// Stages [`CSVParser::remove_column`].
+    pub fn remove_column(&mut self, col_index: usize) -> &mut Self {
+        self.stage(Staged::RemoveColumn(col_index))
+    }
+
+    /// Stages [`CSVParser::swap_columns`].
+    pub fn swap_columns(&mut self, a: usize, b: usize) -> &mut Self {
+        self.stage(Staged::SwapColumns(a, b))
+    }
+
+    /// Stages [`CSVParser::sort_by_keys`].
+    pub fn sort_by_keys(&mut self, keys: &[SortKey]) -> &mut Self {
+        self.stage(Staged::Sort(keys.to_vec()))
+    }
+
+    /// Stages [`CSVParser::rename_column`].
+    pub fn rename_column(&mut self, name: &str, new_name: &str) -> &mut Self {
+        self.stage(Staged::RenameColumn(name.to_string(), new_name.to_string()))
+    }
+
+    /// The number of staged edits.
+    pub fn len(&self) -> usize {
+        self.staged.len()
+    }
+
+    /// Whether no edits are staged.
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    /// Applies the staged edits in order. If one fails, those before it are
+    /// taken back, leaving the parser and its history as they were, and
+    /// [`CsvError::Rollback`] says which edit failed and why.
+    ///
+    /// Committed edits appear in [`CSVParser::history`] one by one.
+    pub fn commit(self) -> Result<(), CsvError> {
+        let staged = self.staged;
+        self.parser.atomically(|parser| {
+            for (index, edit) in staged.into_iter().enumerate() {
+                let description = edit.to_string();
+                edit.apply(parser).map_err(|err| CsvError::Rollback {
+                    edit: index,
+                    description,
+                    error: Box::new(err),
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    fn stage(&mut self, edit: Staged<'a>) -> &mut Self {
+        self.staged.push(edit);
+        self
+    }
+}
+
+impl Staged<'_> {
+    fn apply(self, parser: &mut CSVParser) -> Result<(), CsvError> {
+        match self {
+            Staged::UpdateCell(row, col, value) => parser.update_cell(row, col, &value),
+            Staged::UpdateByName(row, name, value) => parser.update_by_name(row, &name, &value),
+            Staged::PushRow(row) => {
+                parser.push_row(row);
+                Ok(())
+            }
+            Staged::InsertRow(index, row) => parser.insert_row(index, row),
+            Staged::RemoveRow(index) => parser.remove_row(index).map(|_| ()),
+            Staged::InsertColumn(index, name, value) => parser.insert_column(index, &name, value),
+            Staged::RemoveColumn(index) => parser.remove_column(index),
+            Staged::SwapColumns(a, b) => parser.swap_columns(a, b),
+            Staged::Sort(keys) => parser.sort_by_keys(&keys),
+            Staged::RenameColumn(name, new_name) => parser.rename_column(&name, &new_name),
+        }
+    }
+}
+
+impl fmt::Display for Staged<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Staged::UpdateCell(row, col, value) => {
+                write!(f, "update_cell({}, {}, {:?})", row, col, value)
+            }
+            Staged::UpdateByName(row, name, value) => {
+                write!(f, "update_by_name({}, {:?}, {:?})", row, name, value)
+            }
+            Staged::PushRow(row) => write!(f, "push_row({:?})", row),
+            Staged::InsertRow(index, row) => write!(f, "insert_row({}, {:?})", index, row),
+            Staged::RemoveRow(index) => write!(f, "remove_row({})", index),
+            Staged::InsertColumn(index, name, _) => {
+                write!(f, "insert_column({}, {:?})", index, name)
+            }
+            Staged::RemoveColumn(index) => write!(f, "remove_column({})", index),
+            Staged::SwapColumns(a, b) => write!(f, "swap_columns({}, {})", a, b),
+            Staged::Sort(keys) => {
+                let columns: Vec<&str> = keys.iter().map(|key| key.column.as_str()).collect();
+                write!(f, "sort_by_keys({:?})", columns)
+            }
+            Staged::RenameColumn(name, new_name) => {
+                write!(f, "rename_column({:?}, {:?})", name, new_name)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{contents, parser, row, rows};
+    use crate::{ColumnType, CsvError};
+
+    const PEOPLE: &str = "name,age\na,1\nb\n";
+
+    #[test]
+    fn commits_every_edit() {
+        let mut parser = parser(PEOPLE);
+        let mut transaction = parser.transaction();
+        transaction
+            .update_by_name(0, "age", "2")
+            .push_row(row(&["c", "3"]))
+            .swap_columns(0, 1);
+        assert_eq!(transaction.len(), 3);
+        transaction.commit().unwrap();
+        assert_eq!(
+            contents(&parser).1,
+            rows(&[&["2", "a"], &["", "b"], &["3", "c"]])
+        );
+        assert_eq!(parser.history().len(), 3);
+    }
+
+    #[test]
+    fn rolls_back_when_an_edit_fails() {
+        let mut parser = parser(PEOPLE);
+        parser.update_cell(0, 1, "5").unwrap();
+        parser.undo();
+        let before = contents(&parser);
+
+        let mut transaction = parser.transaction();
+        transaction
+            .remove_column(0)
+            .insert_column(1, "n", |row| row.len().to_string())
+            .rename_column("age", "years")
+            .remove_row(7)
+            .push_row(row(&["c"]));
+        let err = transaction.commit().unwrap_err();
+
+        let CsvError::Rollback {
+            edit,
+            description,
+            error,
+        } = err
+        else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert_eq!((edit, description.as_str()), (3, "remove_row(7)"));
+        assert!(matches!(*error, CsvError::RowOutOfBounds { row: 7, .. }));
+        assert_eq!(contents(&parser), before);
+        assert!(parser.history().is_empty());
+        // What could be redone before the transaction still can be.
+        parser.redo().unwrap();
+        assert_eq!(parser.get_row(0), Some(&row(&["a", "5"])));
+    }
+
+    #[test]
+    fn dropping_discards_staged_edits() {
+        let mut parser = parser(PEOPLE);
+        parser.transaction().remove_row(0).remove_column(1);
+        assert_eq!(parser.len(), 2);
+        assert!(parser.history().is_empty());
+    }
+
+    #[test]
+    fn keeps_the_column_types_when_rolled_back() {
+        let mut parser = parser(PEOPLE);
+        let types = vec![ColumnType::Text, ColumnType::Integer];
+        parser.set_column_types(types.clone());
+        let mut transaction = parser.transaction();
+        transaction
+            .insert_column(0, "id", |_| "1".to_string())
+            .remove_column(2)
+            .remove_row(7);
+        assert!(transaction.commit().is_err());
+        assert_eq!(parser.column_types(), Some(types.as_slice()));
+    }
+}
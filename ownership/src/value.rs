@@ -0,0 +1,299 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::date::Date;
+use crate::{de, Row};
+
+// Share of a column's non-null cells that must fit a type for it to be
+// inferred; the rest are counted as failures.
+const INFERENCE_THRESHOLD: f64 = 0.9;
+const NULLS: [&str; 6] = ["", "na", "n/a", "null", "none", "nil"];
+// The types inference picks from, most specific first.
+const CANDIDATES: [ColumnType; 5] = [
+    ColumnType::Integer,
+    ColumnType::Float,
+    ColumnType::Boolean,
+    ColumnType::Date,
+    ColumnType::DateTime,
+];
+
+/// The type of a column's cells. In a [`Schema`](crate::Schema) file it is
+/// written in lowercase: `integer`, `float`, `boolean`, `date`, `datetime`
+/// or `text`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    /// Whole numbers that fit in an `i64`.
+    Integer,
+    /// Any numbers.
+    ///
+    /// Neither numeric type takes a number written with a leading zero,
+    /// such as the zip code `02134`, since such cells are usually codes
+    /// whose zeros matter.
+    Float,
+    /// `true` or `false`, also written `yes` or `no`, in any case.
+    Boolean,
+    /// Dates without a time of day.
+    Date,
+    /// Dates, some or all with a time of day.
+    DateTime,
+    /// Anything.
+    Text,
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ColumnType::Integer => "integer",
+            ColumnType::Float => "float",
+            ColumnType::Boolean => "boolean",
+            ColumnType::Date => "date",
+            ColumnType::DateTime => "datetime",
+            ColumnType::Text => "text",
+        })
+    }
+}
+
+/// A cell read as its column's type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// An empty cell, or one reading `NA`, `N/A`, `null`, `none` or `nil` in
+    /// any case.
+    Null,
+    /// A whole number.
+    Integer(i64),
+    /// A number.
+    Float(f64),
+    /// A boolean.
+    Boolean(bool),
+    /// A date, with or without a time of day.
+    Date(Date),
+    /// Text, including cells that do not fit their column's type.
+    Text(String),
+}
+
+impl Value {
+    /// Reads `cell` as the most specific type it fits.
+    pub fn parse(cell: &str) -> Self {
+        [
+            ColumnType::Integer,
+            ColumnType::Float,
+            ColumnType::Boolean,
+            ColumnType::DateTime,
+        ]
+        .into_iter()
+        .find_map(|column_type| Value::parse_as(cell, column_type))
+        .unwrap_or_else(|| Value::Text(cell.to_string()))
+    }
+
+    /// Reads `cell` as `column_type`, or returns `None` if it does not fit.
+    /// Null cells fit every type.
+    pub fn parse_as(cell: &str, column_type: ColumnType) -> Option<Self> {
+        let trimmed = cell.trim();
+        if is_null(trimmed) {
+            return Some(Value::Null);
+        }
+        match column_type {
+            ColumnType::Integer | ColumnType::Float if has_leading_zero(trimmed) => None,
+            ColumnType::Integer => trimmed.parse().ok().map(Value::Integer),
+            ColumnType::Float => parse_float(trimmed).map(Value::Float),
+            ColumnType::Boolean => de::parse_bool(trimmed).map(Value::Boolean),
+            ColumnType::Date => Date::parse(trimmed)
+                .filter(|date| date.time.is_none())
+                .map(Value::Date),
+            ColumnType::DateTime => Date::parse(trimmed).map(Value::Date),
+            ColumnType::Text => Some(Value::Text(cell.to_string())),
+        }
+    }
+
+    /// Whether this is [`Value::Null`].
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+/// Written as it would be in a cell; null is empty.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Integer(number) => write!(f, "{}", number),
+            Value::Float(number) => write!(f, "{}", number),
+            Value::Boolean(boolean) => write!(f, "{}", boolean),
+            Value::Date(date) => write!(f, "{}", date),
+            Value::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+/// What [`CSVParser::infer_types`](crate::CSVParser::infer_types) found out
+/// about one column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnInference {
+    /// The column's header, or its index if there are no headers.
+    pub name: String,
+    /// The inferred type.
+    pub column_type: ColumnType,
+    /// The type other than text that the most non-null cells fit, if any
+    /// fit one. It is the inferred type unless too few cells fit it, as in a
+    /// column of numbers with a few words mixed in.
+    pub best_fit: Option<ColumnType>,
+    /// How many cells are null.
+    pub nulls: usize,
+    /// How many non-null cells do not fit [`ColumnInference::best_fit`].
+    pub failures: usize,
+}
+
+/// Infers the type of column `col` of `data`: the most specific type that at
+/// least 90% of its non-null cells fit, or text.
+pub(crate) fn infer_column(name: String, data: &[Row], col: usize) -> ColumnInference {
+    let cells = data
+        .iter()
+        .map(|row| row.get(col).map_or("", String::as_str));
+    let mut nulls = 0;
+    let mut non_null = 0;
+    let mut fits = [0usize; CANDIDATES.len()];
+    for cell in cells {
+        if is_null(cell.trim()) {
+            nulls += 1;
+            continue;
+        }
+        non_null += 1;
+        for (fit, column_type) in fits.iter_mut().zip(CANDIDATES) {
+            if Value::parse_as(cell, column_type).is_some() {
+                *fit += 1;
+            }
+        }
+    }
+
+    // The first candidate with the most fits wins, so integers are preferred
+    // to floats and dates to datetimes when every cell fits both.
+    let best = CANDIDATES
+        .into_iter()
+        .zip(fits)
+        .rev()
+        .max_by_key(|&(_, fit)| fit)
+        .filter(|&(_, fit)| fit > 0);
+    let column_type = match best {
+        Some((column_type, fit)) if fit as f64 >= non_null as f64 * INFERENCE_THRESHOLD => {
+            column_type
+        }
+        _ => ColumnType::Text,
+    };
+    ColumnInference {
+        name,
+        column_type,
+        best_fit: best.map(|(column_type, _)| column_type),
+        nulls,
+        failures: best.map_or(0, |(_, fit)| non_null - fit),
+    }
+}
+
+/// Whether a trimmed cell is null.
+pub(crate) fn is_null(trimmed: &str) -> bool {
+    NULLS.iter().any(|null| trimmed.eq_ignore_ascii_case(null))
+}
+
+/// Whether a number is written with a zero before another digit, as in
+/// `02134` or `-007`, but not `0` or `0.5`.
+fn has_leading_zero(trimmed: &str) -> bool {
+    let digits = trimmed.trim_start_matches(['-', '+']).as_bytes();
+    digits.len() > 1 && digits[0] == b'0' && digits[1].is_ascii_digit()
+}
+
+/// Parses a decimal number, but not words such as `inf` or `NaN` that
+/// `f64::from_str` also accepts.
+fn parse_float(trimmed: &str) -> Option<f64> {
+    let numeric = trimmed
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'));
+    if numeric && trimmed.chars().any(|c| c.is_ascii_digit()) {
+        trimmed.parse().ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::row;
+
+    fn infer(cells: &[&str]) -> ColumnInference {
+        let data: Vec<Row> = cells.iter().map(|cell| row(&[cell])).collect();
+        infer_column("c".to_string(), &data, 0)
+    }
+
+    #[test]
+    fn reads_a_cell_as_the_most_specific_type_it_fits() {
+        assert_eq!(Value::parse("42"), Value::Integer(42));
+        assert_eq!(Value::parse("-4.5e1"), Value::Float(-45.0));
+        assert_eq!(Value::parse(" YES "), Value::Boolean(true));
+        assert!(matches!(Value::parse("2024-01-05"), Value::Date(_)));
+        assert_eq!(Value::parse("N/A"), Value::Null);
+        assert_eq!(Value::parse("inf"), Value::Text("inf".to_string()));
+    }
+
+    #[test]
+    fn keeps_numbers_with_leading_zeros_as_text() {
+        for cell in ["02134", "-007", "00.5"] {
+            assert_eq!(Value::parse(cell), Value::Text(cell.to_string()));
+            assert_eq!(Value::parse_as(cell, ColumnType::Float), None);
+        }
+        assert_eq!(Value::parse("0"), Value::Integer(0));
+        assert_eq!(Value::parse("-0.5"), Value::Float(-0.5));
+        assert_eq!(infer(&["02134", "10001"]).column_type, ColumnType::Text);
+    }
+
+    #[test]
+    fn reads_booleans_as_deserialization_does() {
+        for (cell, expected) in [("True", true), ("no", false), ("YES", true)] {
+            assert_eq!(
+                Value::parse_as(cell, ColumnType::Boolean),
+                Some(Value::Boolean(expected))
+            );
+        }
+        assert_eq!(Value::parse_as("1", ColumnType::Boolean), None);
+    }
+
+    #[test]
+    fn prefers_integers_to_floats_and_dates_to_datetimes() {
+        assert_eq!(infer(&["1", "2", "3"]).column_type, ColumnType::Integer);
+        assert_eq!(infer(&["1", "2.5"]).column_type, ColumnType::Float);
+        let dates = infer(&["2024-01-01", "2024-02-01"]);
+        assert_eq!(dates.column_type, ColumnType::Date);
+        let datetimes = infer(&["2024-01-01", "2024-02-01T10:00"]);
+        assert_eq!(datetimes.column_type, ColumnType::DateTime);
+    }
+
+    #[test]
+    fn needs_nine_in_ten_cells_to_fit() {
+        let mut cells = vec!["1"; 9];
+        cells.push("one");
+        let inference = infer(&cells);
+        assert_eq!(inference.column_type, ColumnType::Integer);
+        assert_eq!(inference.failures, 1);
+
+        cells.push("two");
+        let inference = infer(&cells);
+        assert_eq!(inference.column_type, ColumnType::Text);
+        assert_eq!(inference.best_fit, Some(ColumnType::Integer));
+        assert_eq!(inference.failures, 2);
+    }
+
+    #[test]
+    fn leaves_nulls_out_of_the_count() {
+        let inference = infer(&["", "NA", "null", "7", "8"]);
+        assert_eq!(inference.column_type, ColumnType::Integer);
+        assert_eq!((inference.nulls, inference.failures), (3, 0));
+
+        let inference = infer(&["", "nil"]);
+        assert_eq!(inference.column_type, ColumnType::Text);
+        assert_eq!(inference.best_fit, None);
+        assert_eq!((inference.nulls, inference.failures), (2, 0));
+
+        let inference = infer(&["red", "blue"]);
+        assert_eq!((inference.best_fit, inference.failures), (None, 0));
+    }
+}
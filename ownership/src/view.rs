@@ -0,0 +1,524 @@
+//! `csv view`: a full-screen viewer and cell editor for plain terminals.
+
+use std::io::{self, Write};
+use std::ops::ControlFlow;
+
+use crossterm::cursor::{self, MoveTo};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use unicode_width::UnicodeWidthStr;
+
+use ownership::{display_cell, CSVParser, CsvError, Edit};
+
+// Widest a column is drawn; the edit prompt shows the whole cell.
+const MAX_COLUMN_WIDTH: usize = 30;
+const HELP: &str =
+    "arrows/hjkl move  g/G first/last row  : jump  / search  n/N next/prev  e edit  u/^R undo/redo  s save  q quit";
+
+/// Shows `parser` full screen until the user quits. Saving writes the whole
+/// file to `path` with [`CSVParser::write_csv`].
+pub fn run(parser: &mut CSVParser, path: &str) -> Result<(), CsvError> {
+    let _terminal = Terminal::enter()?;
+    let mut viewer = Viewer::new(parser, path);
+    let mut stdout = io::stdout().lock();
+    loop {
+        viewer.draw(&mut stdout)?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Release && viewer.handle_key(key).is_break() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Raw mode on the alternate screen, restored on drop even after a panic.
+struct Terminal;
+
+impl Terminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        if let Err(err) = execute!(io::stdout(), EnterAlternateScreen, cursor::Hide) {
+            let _ = terminal::disable_raw_mode();
+            return Err(err);
+        }
+        Ok(Terminal)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Prompt {
+    Jump,
+    Search,
+    Edit,
+}
+
+impl Prompt {
+    fn label(self) -> &'static str {
+        match self {
+            Prompt::Jump => "jump to row[,column]: ",
+            Prompt::Search => "search: ",
+            Prompt::Edit => "edit: ",
+        }
+    }
+}
+
+struct Viewer<'a> {
+    parser: &'a mut CSVParser,
+    path: &'a str,
+    labels: Vec<String>,
+    widths: Vec<usize>,
+    // The selected cell.
+    row: usize,
+    col: usize,
+    // The first row and column on screen.
+    top: usize,
+    left: usize,
+    // Rows that fit on screen, as of the last draw.
+    page: usize,
+    prompt: Option<(Prompt, String)>,
+    search: Option<String>,
+    message: String,
+    modified: bool,
+    confirm_quit: bool,
+}
+
+impl<'a> Viewer<'a> {
+    fn new(parser: &'a mut CSVParser, path: &'a str) -> Self {
+        let columns = parser.column_count();
+        // Without a header row, columns are labelled by index, which is what
+        // `csv get` and the jump prompt take.
+        let labels = (0..columns)
+            .map(
+                |col| match parser.headers().and_then(|headers| headers.get(col)) {
+                    Some(name) => name.clone(),
+                    None => col.to_string(),
+                },
+            )
+            .collect();
+        let mut viewer = Viewer {
+            parser,
+            path,
+            labels,
+            widths: vec![0; columns],
+            row: 0,
+            col: 0,
+            top: 0,
+            left: 0,
+            page: 1,
+            prompt: None,
+            search: None,
+            message: "press ? for help".to_string(),
+            modified: false,
+            confirm_quit: false,
+        };
+        for col in 0..columns {
+            viewer.measure(col);
+        }
+        viewer
+    }
+
+    fn columns(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn measure(&mut self, col: usize) {
+        let widest = (0..self.parser.len())
+            .filter_map(|row| self.parser.get_cell(row, col))
+            .chain([self.labels[col].as_str()])
+            .map(|cell| display_cell(cell, Some(MAX_COLUMN_WIDTH)).width())
+            .max()
+            .unwrap_or(0);
+        self.widths[col] = widest.max(1);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> ControlFlow<()> {
+        if let Some((prompt, mut input)) = self.prompt.take() {
+            match key.code {
+                KeyCode::Enter => self.submit(prompt, input),
+                KeyCode::Esc => self.message.clear(),
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.prompt = Some((prompt, input));
+                }
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    input.push(c);
+                    self.prompt = Some((prompt, input));
+                }
+                _ => self.prompt = Some((prompt, input)),
+            }
+            return ControlFlow::Continue(());
+        }
+
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let confirm_quit = std::mem::take(&mut self.confirm_quit);
+        self.message.clear();
+        let last_row = self.parser.len().saturating_sub(1);
+        let last_col = self.columns().saturating_sub(1);
+        match key.code {
+            KeyCode::Char('c') if control => return ControlFlow::Break(()),
+            KeyCode::Char('s') if control => self.save(),
+            KeyCode::Char('r') if control => {
+                let edit = self.parser.redo().cloned();
+                self.show_edit(edit, "redo");
+            }
+            KeyCode::Char('u') => {
+                let edit = self.parser.undo().cloned();
+                self.show_edit(edit, "undo");
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if !self.modified || confirm_quit {
+                    return ControlFlow::Break(());
+                }
+                self.message = "unsaved changes; press q again to quit without saving".into();
+                self.confirm_quit = true;
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.row = self.row.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.row = (self.row + 1).min(last_row),
+            KeyCode::Left | KeyCode::Char('h') => self.col = self.col.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') => self.col = (self.col + 1).min(last_col),
+            KeyCode::PageUp => self.row = self.row.saturating_sub(self.page),
+            KeyCode::PageDown => self.row = (self.row + self.page).min(last_row),
+            KeyCode::Home | KeyCode::Char('0') => self.col = 0,
+            KeyCode::End | KeyCode::Char('$') => self.col = last_col,
+            KeyCode::Char('g') => self.row = 0,
+            KeyCode::Char('G') => self.row = last_row,
+            KeyCode::Char(':') => self.prompt = Some((Prompt::Jump, String::new())),
+            KeyCode::Char('/') => self.prompt = Some((Prompt::Search, String::new())),
+            KeyCode::Char('n') => self.find_next(true),
+            KeyCode::Char('N') => self.find_next(false),
+            KeyCode::Enter | KeyCode::Char('e') => match self.parser.get_cell(self.row, self.col) {
+                Some(cell) => self.prompt = Some((Prompt::Edit, cell.to_string())),
+                None => self.message = "no cell here to edit".into(),
+            },
+            KeyCode::Char('s') => self.save(),
+            KeyCode::Char('?') => self.message = HELP.into(),
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn submit(&mut self, prompt: Prompt, input: String) {
+        match prompt {
+            Prompt::Jump => self.jump(&input),
+            Prompt::Search if input.is_empty() => {}
+            Prompt::Search => {
+                self.search = Some(input);
+                self.find_next(true);
+            }
+            Prompt::Edit => match self.parser.update_cell(self.row, self.col, &input) {
+                Ok(()) => {
+                    self.modified = true;
+                    self.measure(self.col);
+                }
+                Err(err) => self.message = err.to_string(),
+            },
+        }
+    }
+
+    /// Moves to `row`, or to `row,column` where the column is a header name
+    /// or else an index.
+    fn jump(&mut self, input: &str) {
+        let (row, col) = match input.split_once([',', ' ']) {
+            Some((row, col)) => (row.trim(), Some(col.trim())),
+            None => (input.trim(), None),
+        };
+        let Ok(row) = row.parse::<usize>() else {
+            self.message = format!("not a row number: {:?}", row);
+            return;
+        };
+        if row >= self.parser.len() {
+            self.message = format!("row {} is past the last row", row);
+            return;
+        }
+        if let Some(col) = col {
+            match ownership::resolve_column(self.parser.headers(), col, self.columns()) {
+                Ok(index) => self.col = index,
+                Err(_) => {
+                    self.message = format!("no column {:?}", col);
+                    return;
+                }
+            }
+        }
+        self.row = row;
+    }
+
+    /// Selects the next cell, in reading order and wrapping around, that
+    /// contains the search text, ignoring case.
+    fn find_next(&mut self, forward: bool) {
+        let Some(search) = &self.search else {
+            self.message = "nothing to search for; press / first".into();
+            return;
+        };
+        let needle = search.to_lowercase();
+        let columns = self.columns();
+        let cells = self.parser.len() * columns;
+        let start = self.row * columns + self.col;
+        for step in 1..=cells {
+            let index = if forward {
+                (start + step) % cells
+            } else {
+                (start + cells - step) % cells
+            };
+            let (row, col) = (index / columns, index % columns);
+            let found = self
+                .parser
+                .get_cell(row, col)
+                .is_some_and(|cell| cell.to_lowercase().contains(&needle));
+            if found {
+                self.row = row;
+                self.col = col;
+                return;
+            }
+        }
+        self.message = format!("no match for {:?}", search);
+    }
+
+    /// Selects the cell an undone or redone edit changed.
+    fn show_edit(&mut self, edit: Option<Edit>, action: &str) {
+        let Some(edit) = edit else {
+            self.message = format!("nothing to {}", action);
+            return;
+        };
+        // The viewer only ever updates cells.
+        if let Edit::UpdateCell { row, col, .. } = edit {
+            self.row = row;
+            self.col = col;
+            self.measure(col);
+        }
+        self.modified = true;
+        self.message = format!("{}: {}", action, edit);
+    }
+
+    fn save(&mut self) {
+        self.message = match self.parser.write_csv(self.path) {
+            Ok(()) => {
+                self.modified = false;
+                format!("saved {}", self.path)
+            }
+            Err(err) => format!("could not save {}: {}", self.path, err),
+        };
+    }
+
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let width = width as usize;
+        // One line each for the frozen header and the status line.
+        self.page = (height as usize).saturating_sub(2).max(1);
+        let gutter = self.parser.len().saturating_sub(1).to_string().len();
+        self.scroll(width.saturating_sub(gutter + 1));
+
+        queue!(out, MoveTo(0, 0), Clear(ClearType::CurrentLine))?;
+        queue!(
+            out,
+            SetAttribute(Attribute::Bold),
+            Print(" ".repeat(gutter))
+        )?;
+        let visible = self.visible_columns(width.saturating_sub(gutter));
+        for &(col, cell_width) in &visible {
+            let selected = col == self.col;
+            self.draw_cell(out, &self.labels[col], cell_width, selected)?;
+        }
+        queue!(out, SetAttribute(Attribute::Reset))?;
+
+        for line in 0..self.page {
+            let row = self.top + line;
+            queue!(
+                out,
+                MoveTo(0, line as u16 + 1),
+                Clear(ClearType::CurrentLine)
+            )?;
+            if row >= self.parser.len() {
+                continue;
+            }
+            queue!(out, Print(format!("{:>gutter$}", row)))?;
+            for &(col, cell_width) in &visible {
+                let cell = self.parser.get_cell(row, col).unwrap_or("");
+                let selected = row == self.row && col == self.col;
+                self.draw_cell(out, cell, cell_width, selected)?;
+            }
+        }
+
+        queue!(
+            out,
+            MoveTo(0, height.saturating_sub(1)),
+            Clear(ClearType::CurrentLine)
+        )?;
+        match &self.prompt {
+            Some((prompt, input)) => {
+                let line = format!("{}{}", prompt.label(), input);
+                queue!(
+                    out,
+                    Print(display_cell(&line, Some(width.saturating_sub(1))))
+                )?;
+                queue!(out, cursor::Show)?;
+            }
+            None => {
+                let status = format!(
+                    "{}{}  row {} of {}  {}  {}",
+                    self.path,
+                    if self.modified { " [+]" } else { "" },
+                    self.row,
+                    self.parser.len(),
+                    self.labels.get(self.col).map(String::as_str).unwrap_or(""),
+                    self.message,
+                );
+                let status = display_cell(&status, Some(width));
+                let padding = " ".repeat(width.saturating_sub(status.width()));
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(status),
+                    Print(padding)
+                )?;
+                queue!(out, SetAttribute(Attribute::Reset), cursor::Hide)?;
+            }
+        }
+        out.flush()
+    }
+
+    /// Adjusts the first row and column on screen so the selected cell is
+    /// visible in `width` columns.
+    fn scroll(&mut self, width: usize) {
+        if self.row < self.top {
+            self.top = self.row;
+        } else if self.row >= self.top + self.page {
+            self.top = self.row + 1 - self.page;
+        }
+        if self.col < self.left {
+            self.left = self.col;
+        }
+        while self.left < self.col {
+            let needed: usize = self.widths[self.left..=self.col]
+                .iter()
+                .map(|width| width + 1)
+                .sum();
+            if needed <= width {
+                break;
+            }
+            self.left += 1;
+        }
+    }
+
+    /// The columns that fit in `width` from the first one on screen, each
+    /// with the width it is drawn at. The last one may be cut short.
+    fn visible_columns(&self, mut width: usize) -> Vec<(usize, usize)> {
+        let mut visible = Vec::new();
+        for col in self.left..self.columns() {
+            // Each cell is preceded by a space.
+            if width < 2 {
+                break;
+            }
+            let cell_width = self.widths[col].min(width - 1);
+            visible.push((col, cell_width));
+            width -= cell_width + 1;
+        }
+        visible
+    }
+
+    fn draw_cell(
+        &self,
+        out: &mut impl Write,
+        cell: &str,
+        width: usize,
+        selected: bool,
+    ) -> io::Result<()> {
+        let text = display_cell(cell, Some(width));
+        let padding = " ".repeat(width.saturating_sub(text.width()));
+        queue!(out, Print(" "))?;
+        if selected {
+            queue!(out, SetAttribute(Attribute::Reverse))?;
+        }
+        queue!(out, Print(text), Print(padding))?;
+        if selected {
+            queue!(out, SetAttribute(Attribute::NoReverse))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn people() -> CSVParser {
+        let mut parser = CSVParser::new().has_headers(true);
+        parser
+            .parse_from("Name,2020,Age\nAnn,x,31\nBob,y,40\n".as_bytes())
+            .unwrap();
+        parser
+    }
+
+    fn press(viewer: &mut Viewer, keys: &str) {
+        for c in keys.chars() {
+            let code = if c == '\n' {
+                KeyCode::Enter
+            } else {
+                KeyCode::Char(c)
+            };
+            let _ = viewer.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn jumps_to_a_header_before_an_index() {
+        let mut parser = people();
+        let mut viewer = Viewer::new(&mut parser, "people.csv");
+        press(&mut viewer, ":1,Age\n");
+        assert_eq!((viewer.row, viewer.col), (1, 2));
+        press(&mut viewer, ":0 2020\n");
+        assert_eq!((viewer.row, viewer.col), (0, 1));
+        press(&mut viewer, ":1,0\n");
+        assert_eq!((viewer.row, viewer.col), (1, 0));
+    }
+
+    #[test]
+    fn stays_put_on_a_bad_jump() {
+        let mut parser = people();
+        let mut viewer = Viewer::new(&mut parser, "people.csv");
+        press(&mut viewer, ":5\n");
+        assert_eq!(viewer.message, "row 5 is past the last row");
+        press(&mut viewer, ":1,Salary\n");
+        assert_eq!(viewer.message, "no column \"Salary\"");
+        assert_eq!((viewer.row, viewer.col), (0, 0));
+    }
+
+    #[test]
+    fn searches_forward_and_back_ignoring_case() {
+        let mut parser = people();
+        let mut viewer = Viewer::new(&mut parser, "people.csv");
+        press(&mut viewer, "/BOB\n");
+        assert_eq!((viewer.row, viewer.col), (1, 0));
+        press(&mut viewer, "/zed\n");
+        assert_eq!(viewer.message, "no match for \"zed\"");
+        press(&mut viewer, "/a\nN");
+        assert_eq!((viewer.row, viewer.col), (0, 0));
+    }
+
+    #[test]
+    fn edits_a_cell_and_asks_before_quitting_unsaved() {
+        let mut parser = people();
+        let mut viewer = Viewer::new(&mut parser, "people.csv");
+        press(&mut viewer, "le");
+        let _ = viewer.handle_key(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE));
+        press(&mut viewer, "z\n");
+        assert!(viewer.modified);
+        assert_eq!(
+            viewer.handle_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE)),
+            ControlFlow::Continue(())
+        );
+        assert_eq!(
+            viewer.handle_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE)),
+            ControlFlow::Break(())
+        );
+        assert_eq!(parser.get_cell(0, 1), Some("z"));
+    }
+}
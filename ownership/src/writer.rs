@@ -0,0 +1,229 @@
+use std::io::{self, BufWriter, Write};
+
+use crate::dialect::{Dialect, Escape};
+use crate::error::CsvError;
+
+/// When fields are wrapped in quotes on output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// Quote only fields containing a delimiter, a quote or a line break.
+    #[default]
+    Necessary,
+    /// Quote every field.
+    Always,
+    /// Quote every field that does not look like a number.
+    NonNumeric,
+    /// Never quote. Special characters are escaped when the dialect has an
+    /// escape character and will not round-trip otherwise.
+    Never,
+}
+
+/// Writes records one at a time to any [`Write`] sink.
+///
+/// Output is buffered; call [`CsvWriter::flush`] to push it through and see
+/// any error, since dropping the writer flushes silently.
+pub struct CsvWriter<W: Write> {
+    sink: BufWriter<W>,
+    dialect: Dialect,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Writes RFC 4180 CSV to `sink`.
+    pub fn new(sink: W) -> Self {
+        CsvWriter {
+            sink: BufWriter::new(sink),
+            dialect: Dialect::default(),
+        }
+    }
+
+    /// Sets the dialect records are written in.
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Writes `row` followed by the dialect's line terminator.
+    pub fn write_record(&mut self, row: &[String]) -> Result<(), CsvError> {
+        write_record(&mut self.sink, row, &self.dialect)?;
+        Ok(())
+    }
+
+    /// Flushes buffered output to the sink.
+    pub fn flush(&mut self) -> Result<(), CsvError> {
+        self.sink.flush()?;
+        Ok(())
+    }
+}
+
+/// Formats `row` as a single record, without a line terminator.
+pub fn format_record(row: &[String], dialect: &Dialect) -> String {
+    let mut line = String::new();
+    for (i, field) in row.iter().enumerate() {
+        if i > 0 {
+            line.push(dialect.delimiter);
+        }
+        // A lone empty field would otherwise be written as a blank line,
+        // which readers skip.
+        let lone_empty = row.len() == 1 && field.is_empty();
+        if dialect.quote_style == QuoteStyle::Never {
+            push_escaped(&mut line, field, dialect);
+        } else if lone_empty || needs_quotes(field, dialect) {
+            push_quoted(&mut line, field, dialect);
+        } else {
+            line.push_str(field);
+        }
+    }
+    line
+}
+
+/// Writes `row` followed by the dialect's line terminator.
+pub fn write_record<W: Write>(writer: &mut W, row: &[String], dialect: &Dialect) -> io::Result<()> {
+    writer.write_all(format_record(row, dialect).as_bytes())?;
+    writer.write_all(dialect.terminator.as_str().as_bytes())
+}
+
+fn push_quoted(line: &mut String, field: &str, dialect: &Dialect) {
+    line.push(dialect.quote);
+    for c in field.chars() {
+        match dialect.escape {
+            Escape::Doubled if c == dialect.quote => line.push(c),
+            Escape::Char(e) if c == dialect.quote || c == e => line.push(e),
+            _ => {}
+        }
+        line.push(c);
+    }
+    line.push(dialect.quote);
+}
+
+fn push_escaped(line: &mut String, field: &str, dialect: &Dialect) {
+    for c in field.chars() {
+        if let Escape::Char(e) = dialect.escape {
+            if is_special(c, dialect) {
+                line.push(e);
+            }
+        }
+        line.push(c);
+    }
+}
+
+fn needs_quotes(field: &str, dialect: &Dialect) -> bool {
+    match dialect.quote_style {
+        QuoteStyle::Necessary => {
+            field.chars().any(|c| is_special(c, dialect))
+                || dialect
+                    .comment
+                    .is_some_and(|prefix| field.starts_with(prefix))
+        }
+        QuoteStyle::Always => true,
+        QuoteStyle::NonNumeric => !is_numeric(field),
+        QuoteStyle::Never => false,
+    }
+}
+
+fn is_special(c: char, dialect: &Dialect) -> bool {
+    c == dialect.delimiter || c == dialect.quote || c == '\n' || c == '\r' || dialect.is_escape(c)
+}
+
+fn is_numeric(field: &str) -> bool {
+    let starts_like_number = field
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.'));
+    starts_like_number && field.parse::<f64>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::LineTerminator;
+    use crate::reader::CsvReader;
+    use crate::testing::{row, rows};
+    use crate::Row;
+
+    fn styled(quote_style: QuoteStyle) -> Dialect {
+        Dialect::default().quote_style(quote_style)
+    }
+
+    #[test]
+    fn quotes_only_where_needed() {
+        let line = format_record(
+            &row(&["a", "b,c", "say \"hi\"", "x\ny"]),
+            &Dialect::default(),
+        );
+        assert_eq!(line, "a,\"b,c\",\"say \"\"hi\"\"\",\"x\ny\"");
+    }
+
+    #[test]
+    fn quotes_a_lone_empty_field() {
+        assert_eq!(format_record(&row(&[""]), &Dialect::default()), "\"\"");
+        assert_eq!(format_record(&row(&["", ""]), &Dialect::default()), ",");
+    }
+
+    #[test]
+    fn follows_the_quote_style() {
+        let fields = row(&["1.5", "-2", "x", "a,b"]);
+        assert_eq!(
+            format_record(&fields, &styled(QuoteStyle::Always)),
+            "\"1.5\",\"-2\",\"x\",\"a,b\""
+        );
+        assert_eq!(
+            format_record(&fields, &styled(QuoteStyle::NonNumeric)),
+            "1.5,-2,\"x\",\"a,b\""
+        );
+        assert_eq!(
+            format_record(&fields, &styled(QuoteStyle::Never)),
+            "1.5,-2,x,a,b"
+        );
+    }
+
+    #[test]
+    fn escapes_with_a_backslash() {
+        let dialect = Dialect::default().escape(Escape::Char('\\'));
+        let line = format_record(&row(&["say \"hi\"", "a\\b"]), &dialect);
+        assert_eq!(line, "\"say \\\"hi\\\"\",\"a\\\\b\"");
+        let never = dialect.quote_style(QuoteStyle::Never);
+        assert_eq!(format_record(&row(&["a,b", "c"]), &never), "a\\,b,c");
+    }
+
+    #[test]
+    fn quotes_a_field_that_would_read_as_a_comment() {
+        let dialect = Dialect::default().comment('#');
+        assert_eq!(format_record(&row(&["#a", "b#"]), &dialect), "\"#a\",b#");
+    }
+
+    #[test]
+    fn writes_the_dialect_terminator() {
+        let dialect = Dialect::tsv().terminator(LineTerminator::CrLf);
+        let mut out = Vec::new();
+        write_record(&mut out, &row(&["a", "b"]), &dialect).unwrap();
+        assert_eq!(out, b"a\tb\r\n");
+    }
+
+    #[test]
+    fn round_trips_through_the_reader() {
+        let records = rows(&[
+            &["plain", "with,comma", "with \"quotes\""],
+            &["multi\nline", "", "crlf\r\nend"],
+            &["back\\slash", "tab\there", "#hash"],
+            &[""],
+        ]);
+        for dialect in [
+            Dialect::default(),
+            Dialect::default().escape(Escape::Char('\\')).comment('#'),
+            Dialect::tsv().terminator(LineTerminator::CrLf),
+        ] {
+            let mut out = Vec::new();
+            let mut writer = CsvWriter::new(&mut out).with_dialect(dialect);
+            for record in &records {
+                writer.write_record(record).unwrap();
+            }
+            writer.flush().unwrap();
+            drop(writer);
+            let read: Vec<Row> = CsvReader::new(out.as_slice())
+                .with_dialect(dialect)
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(read, records);
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/data.csv");
+
+/// Runs `csv` with `args`, feeding it `stdin`.
+fn csv(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_csv"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("csv should start");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// A path in the temp directory named after the test that uses it.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("csv-cli-{}-{}.csv", std::process::id(), name))
+}
+
+#[test]
+fn gets_a_cell_by_index_or_header() {
+    let output = csv(&["get", DATA, "2", "0"], "");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "Michael Johnson\n");
+
+    let output = csv(&["get", DATA, "2", "City"], "");
+    assert_eq!(stdout(&output), "Paris\n");
+
+    let output = csv(&["--no-headers", "get", DATA, "2", "0"], "");
+    assert_eq!(stdout(&output), "Jane Smith\n");
+}
+
+#[test]
+fn prefers_a_header_that_looks_like_an_index() {
+    let output = csv(&["get", "-", "0", "2020"], "2019,2020\n1,2\n");
+    assert_eq!(stdout(&output), "2\n");
+}
+
+#[test]
+fn reports_a_malformed_row_before_the_one_asked_for() {
+    let output = csv(&["get", "-", "1", "0"], "h\n\"a\nb\n");
+    assert_eq!(output.status.code(), Some(65));
+}
+
+#[test]
+fn sets_a_cell_in_place() {
+    let path = temp_path("set");
+    fs::write(&path, "Name,Age\nAnn,31\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = csv(&["set", path, "0", "Age", "32", "-o", path], "");
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(path).unwrap(), "Name,Age\nAnn,32\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn keeps_only_the_first_header_when_concatenating() {
+    let path = temp_path("cat");
+    fs::write(&path, "Name,Age\nBob,40\n").unwrap();
+
+    let output = csv(&["cat", "-", path.to_str().unwrap()], "Name,Age\nAnn,31\n");
+    assert_eq!(stdout(&output), "Name,Age\nAnn,31\nBob,40\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn prints_the_first_and_last_rows() {
+    let output = csv(&["head", "-n", "1", DATA], "");
+    assert_eq!(
+        stdout(&output),
+        "Name,Age,City,Profession\nJohn Doe,32,New York,Engineer\n"
+    );
+
+    let output = csv(&["--no-headers", "tail", "-n", "2", "-"], "a\nb\nc\n");
+    assert_eq!(stdout(&output), "b\nc\n");
+}
+
+#[test]
+fn counts_rows_past_the_header() {
+    assert_eq!(stdout(&csv(&["count", DATA], "")), "10\n");
+    assert_eq!(stdout(&csv(&["--no-headers", "count", DATA], "")), "11\n");
+}
+
+#[test]
+fn reads_other_delimiters() {
+    let output = csv(&["-d", "tab", "get", "-", "0", "y"], "x\ty\na\tb\n");
+    assert_eq!(stdout(&output), "b\n");
+}
+
+#[test]
+fn exits_with_a_code_for_each_kind_of_failure() {
+    let code = |args: &[&str]| csv(args, "").status.code();
+
+    assert_eq!(code(&["get", DATA, "99", "0"]), Some(1));
+    assert_eq!(code(&["get", DATA, "0", "Salary"]), Some(1));
+    assert_eq!(code(&["get", DATA, "0", "4"]), Some(1));
+    assert_eq!(code(&["get", DATA, "row", "0"]), Some(2));
+    assert_eq!(code(&["frobnicate"]), Some(2));
+    assert_eq!(code(&["count", "no/such/file.csv"]), Some(66));
+}
+
+#[test]
+fn prints_a_page_of_a_table() {
+    let output = csv(
+        &["table", "-n", "1", "--page", "2", "--plain", "-"],
+        "Name,Age\nAnn,31\nBob,40\n",
+    );
+    assert_eq!(
+        stdout(&output),
+        "Name  Age\n----  ---\nBob    40\nrows 2-2 of 2\n"
+    );
+}
+
+#[test]
+fn filters_rows_by_an_expression() {
+    let output = csv(&["filter", DATA, "Age > 30 && City != \"Paris\""], "");
+    let names: Vec<String> = stdout(&output)
+        .lines()
+        .map(|line| line.split(',').next().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "Name",
+            "John Doe",
+            "David Wilson",
+            "Robert Taylor",
+            "Daniel Martinez",
+            "Sophia Thompson"
+        ]
+    );
+}
+
+#[test]
+fn rejects_a_malformed_filter_as_a_usage_error() {
+    let output = csv(&["filter", DATA, "Age >"], "");
+    assert_eq!(output.status.code(), Some(2));
+    let output = csv(&["filter", DATA, "Salary > 3"], "");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn sorts_by_several_keys_keeping_the_header_first() {
+    let output = csv(
+        &["sort", "-k", "Age:desc", "-k", "Name", "-"],
+        "Name,Age\nCid,9\nBob,31\nAnn,31\nDan,\n",
+    );
+    assert_eq!(stdout(&output), "Name,Age\nAnn,31\nBob,31\nCid,9\nDan,\n");
+    let output = csv(&["sort", "-k", ":desc", DATA], "");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn groups_rows_and_summarizes_them() {
+    let output = csv(
+        &["group", "-b", "Team", "-a", "count", "-a", "mean:Age", "-"],
+        "Team,Age\nred,30\nblue,20\nred,40\n",
+    );
+    assert_eq!(
+        stdout(&output),
+        "Team,count,mean(Age)\nred,2,35\nblue,1,20\n"
+    );
+    let output = csv(&["group", "-b", "Team", "-a", "median:Age", "-"], "");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn joins_a_streamed_file_to_one_in_memory() {
+    let path = temp_path("join");
+    fs::write(&path, "Person,Salary\nJane Smith,50\nNobody,10\n").unwrap();
+
+    let output = csv(
+        &[
+            "join",
+            "-",
+            path.to_str().unwrap(),
+            "--on",
+            "Name",
+            "--right-on",
+            "Person",
+            "-k",
+            "left",
+        ],
+        "Name,Age\nJohn Doe,32\nJane Smith,28\n",
+    );
+    assert_eq!(
+        stdout(&output),
+        "Name,Age,Salary\nJohn Doe,32,\nJane Smith,28,50\n"
+    );
+
+    let output = csv(
+        &[
+            "join",
+            "-",
+            path.to_str().unwrap(),
+            "--on",
+            "Name",
+            "--right-on",
+            "Person",
+        ],
+        "Name\nJane Smith,28\n",
+    );
+    assert_eq!(output.status.code(), Some(65));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn reports_each_column_type() {
+    let output = csv(
+        &["types", "-"],
+        "Zip,Age,Joined\n02134,31,2020-01-01\n10001,n/a,soon\n",
+    );
+    assert_eq!(
+        stdout(&output),
+        "column,type,best_fit,nulls,failures\n\
+         Zip,text,integer,0,1\n\
+         Age,integer,integer,1,0\n\
+         Joined,text,date,0,1\n"
+    );
+}
+
+#[test]
+fn validates_a_file_against_a_schema() {
+    let schema = temp_path("schema").with_extension("toml");
+    fs::write(
+        &schema,
+        "[[columns]]\nname = \"Age\"\ntype = \"integer\"\nmin = 0\nmax = 150\n",
+    )
+    .unwrap();
+    let schema_arg = schema.to_str().unwrap();
+
+    let output = csv(
+        &["validate", "--schema", schema_arg, "-"],
+        "Name,Age\nAnn,31\n",
+    );
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+
+    let output = csv(
+        &["validate", "--schema", schema_arg, "-"],
+        "Name,Age\nAnn,151\nBob,old\n",
+    );
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(
+        stdout(&output),
+        "row 0, column Age: \"151\" is out of range, expected 0 to 150\n\
+         row 1, column Age: expected integer, found \"old\"\n"
+    );
+
+    fs::write(&schema, "[[columns]]\nname = \"Age\"\npattern = \"(\"\n").unwrap();
+    let output = csv(
+        &["validate", "--schema", schema_arg, "-"],
+        "Name,Age\nAnn,31\n",
+    );
+    assert_eq!(output.status.code(), Some(78));
+    fs::remove_file(schema).unwrap();
+}
+
+#[test]
+fn handles_ragged_rows_as_asked() {
+    let input = "Name,Age\nAnn,31\nBob\nCid,40,extra\n";
+    let output = csv(&["--ragged", "fit", "cat", "-"], input);
+    assert_eq!(stdout(&output), "Name,Age\nAnn,31\nBob,\nCid,40\n");
+
+    let output = csv(&["--ragged", "error", "cat", "-"], input);
+    assert_eq!(output.status.code(), Some(65));
+
+    let output = csv(&["--ragged", "quarantine", "count", "-"], input);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "1\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.lines().count(), 2);
+    assert!(stderr.starts_with("csv: -: "), "{}", stderr);
+    assert!(stderr.contains("quarantined: Bob\n"), "{}", stderr);
+
+    let output = csv(
+        &["--ragged", "quarantine", "sort", "-k", "Name", "-"],
+        input,
+    );
+    assert_eq!(stdout(&output), "Name,Age\nAnn,31\n");
+}
+
+#[test]
+fn converts_rows_to_and_from_json() {
+    let output = csv(&["json", "--typed", "--lines", "-"], "Name,Age\nAnn,31\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "{\"Name\":\"Ann\",\"Age\":31}\n");
+
+    let output = csv(
+        &["from-json", "-"],
+        r#"[{"Name": "Ann", "address": {"city": "Rome"}}, {"Name": "Bob"}]"#,
+    );
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "Name,address.city\nAnn,Rome\nBob,\n");
+
+    let output = csv(&["from-json", "--lines", "-"], "{\"Name\": \"Ann\"}\n[]\n");
+    assert_eq!(output.status.code(), Some(65));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 2: expected an object"), "{}", stderr);
+}
+
+#[test]
+fn exports_a_table_as_markup() {
+    let input = "Name,Age\nA|n,31\n";
+    let output = csv(&["export", "-f", "markdown", "-"], input);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "| Name | Age |\n| --- | ---: |\n| A\\|n | 31 |\n"
+    );
+
+    let output = csv(&["export", "-f", "html", "-"], input);
+    assert!(stdout(&output).contains("<tr><td>A|n</td><td>31</td></tr>"));
+
+    let output = csv(&["export", "-f", "latex", "-"], input);
+    assert!(stdout(&output).contains("A\\textbar{}n & 31 \\\\\n"));
+
+    let output = csv(&["export", "-f", "pdf", "-"], input);
+    assert_eq!(output.status.code(), Some(2));
+}